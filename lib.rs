@@ -5,7 +5,52 @@ mod agenda {
     use ink::prelude::string::String;
     use ink::prelude::string::ToString;
     use ink::prelude::vec::Vec;
-    use ink::storage::Mapping;
+    use ink::storage::traits::StorageKey;
+    use ink::storage::{Lazy, Mapping};
+
+    const MS_POR_DIA: u64 = 86_400_000;
+    const MAX_POR_PAGINA: u32 = 100;
+    const MS_POR_MINUTO: u64 = 60_000;
+    const MINUTOS_POR_DIA: i64 = 1440;
+    const MAX_LEMBRETES: usize = 10;
+    const MAX_MOTIVO: usize = 200;
+    /// Maior quantidade de telefones, e-mails ou endereços de um contato.
+    const MAX_ENTRADAS_CONTATO: usize = 10;
+    const MAX_EMAIL: usize = 254;
+    const MAX_CAMPO_ENDERECO: usize = 200;
+    const MAX_NOME_CATEGORIA: usize = 50;
+    const MAX_NOME_GRUPO: usize = 50;
+    /// Maior quantidade de membros de um grupo, para limitar o custo de
+    /// adicioná-lo inteiro a um compromisso.
+    const MAX_MEMBROS_GRUPO: usize = 100;
+    const MAX_TAG: usize = 32;
+    const MAX_TAGS: usize = 20;
+    const MAX_DESCRICAO: usize = 1000;
+    const MAX_LOCAL: usize = 200;
+    const MAX_LINK: usize = 300;
+    /// Maior duração aceita para um compromisso, em minutos: 7 dias. Limita
+    /// quantos dias anteriores a busca por conflitos precisa examinar.
+    const MAX_DURACAO: i32 = 7 * MINUTOS_POR_DIA as i32;
+    /// Maior antecedência aceita para um lembrete: 30 dias.
+    const MAX_ANTECEDENCIA: u32 = 30 * MINUTOS_POR_DIA as u32;
+    /// Maior intervalo aceito pelas consultas de disponibilidade.
+    const MAX_DIAS_CONSULTA: u64 = 90;
+    /// Maior período, em dias, aceito pelas estatísticas de compromissos.
+    const MAX_DIAS_ESTATISTICAS: i64 = 366;
+    /// Quantos dias cada bloco do índice de dias com compromissos agrupa.
+    const DIAS_POR_BLOCO: i64 = 64;
+    /// Por quantos dias, por padrão, os registros excluídos ficam na lixeira.
+    const RETENCAO_PADRAO: u32 = 30;
+    const MAX_RETENCAO: u32 = 3650;
+    /// Quantas entradas de cada fila da lixeira uma purga examina, para
+    /// limitar o gás de quem a dispara.
+    const MAX_PURGA: u32 = 20;
+    /// Quantas revisões de cada registro são guardadas, por padrão.
+    const PROFUNDIDADE_PADRAO: u32 = 10;
+    const MAX_PROFUNDIDADE: u32 = 50;
+    /// Tamanho máximo padrão das operações em lote, para limitar o gás.
+    const LOTE_PADRAO: u32 = 50;
+    const MAX_LOTE: u32 = 500;
 
     // ----- Contatos -----
 
@@ -19,6 +64,34 @@ mod agenda {
         Familiar,
         #[default]
         Colega,
+        /// Categoria criada pelo dono da agenda, identificada pelo seu id.
+        Personalizada(u32),
+    }
+
+    impl Categoria {
+        const PADRAO: [Categoria; 3] = [Categoria::Amigo, Categoria::Familiar, Categoria::Colega];
+    }
+
+    /// Nome e cor de uma categoria. A cor é guardada no formato `#rrggbb`.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct DadosCategoria {
+        pub nome: String,
+        pub cor: String,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct InfoCategoria {
+        pub categoria: Categoria,
+        pub nome: String,
+        pub cor: String,
     }
 
     #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
@@ -28,10 +101,157 @@ mod agenda {
     )]
     pub struct Contato {
         pub nome: String,
+        /// Número do telefone principal, espelhado de `telefones`.
         pub telefone: String,
         pub idade: u32,
         pub data_aniversario: String,
         pub categoria: Categoria,
+        pub telefones: Vec<Telefone>,
+        pub emails: Vec<Email>,
+        pub enderecos: Vec<Endereco>,
+        /// Tags normalizadas, em ordem alfabética.
+        pub tags: Vec<String>,
+    }
+
+    /// Rótulo que identifica o uso de um telefone, e-mail ou endereço.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Rotulo {
+        #[default]
+        Celular,
+        Trabalho,
+        Casa,
+        Outro,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Telefone {
+        pub rotulo: Rotulo,
+        pub numero: String,
+        pub principal: bool,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Email {
+        pub rotulo: Rotulo,
+        pub endereco: String,
+        pub principal: bool,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Endereco {
+        pub rotulo: Rotulo,
+        pub logradouro: String,
+        pub cidade: String,
+        pub estado: String,
+        pub cep: String,
+        pub principal: bool,
+    }
+
+    /// Entrada de uma lista do contato em que apenas uma é a principal.
+    trait EntradaDeContato {
+        fn principal(&self) -> bool;
+        fn marcar_principal(&mut self, principal: bool);
+    }
+
+    impl EntradaDeContato for Telefone {
+        fn principal(&self) -> bool {
+            self.principal
+        }
+        fn marcar_principal(&mut self, principal: bool) {
+            self.principal = principal;
+        }
+    }
+
+    impl EntradaDeContato for Email {
+        fn principal(&self) -> bool {
+            self.principal
+        }
+        fn marcar_principal(&mut self, principal: bool) {
+            self.principal = principal;
+        }
+    }
+
+    impl EntradaDeContato for Endereco {
+        fn principal(&self) -> bool {
+            self.principal
+        }
+        fn marcar_principal(&mut self, principal: bool) {
+            self.principal = principal;
+        }
+    }
+
+    /// Aniversário de um contato que acontece nos próximos dias.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AniversarioProximo {
+        pub contato_id: u32,
+        pub nome: String,
+        /// Data em que o aniversário será comemorado, no formato dd/mm/aaaa.
+        pub data: String,
+        pub dias_restantes: u32,
+        pub idade_a_completar: u32,
+    }
+
+    /// Grupo nomeado de contatos, como "Time de Vendas".
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Grupo {
+        pub nome: String,
+        /// Ids dos contatos do grupo, em ordem crescente.
+        pub membros: Vec<u32>,
+    }
+
+    /// Alteração parcial de um contato: apenas os campos preenchidos são
+    /// validados e gravados.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AlteracaoContato {
+        pub nome: Option<String>,
+        /// Novo número do telefone principal.
+        pub telefone: Option<String>,
+        pub idade: Option<u32>,
+        pub data_aniversario: Option<String>,
+        pub categoria: Option<Categoria>,
+    }
+
+    /// Par de contatos que parecem ser a mesma pessoa.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PossivelDuplicado {
+        pub contato_a: u32,
+        pub contato_b: u32,
+        /// Os dois contatos compartilham algum telefone.
+        pub mesmo_telefone: bool,
+        /// Os nomes normalizados e as datas de aniversário são iguais.
+        pub mesmo_nome_e_aniversario: bool,
     }
 
     // ----- Compromissos -----
@@ -48,6 +268,33 @@ mod agenda {
         Baixa,
     }
 
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum StatusCompromisso {
+        #[default]
+        Agendado,
+        Confirmado,
+        EmAndamento,
+        Concluido,
+        Cancelado,
+    }
+
+    impl StatusCompromisso {
+        /// Indica se o ciclo de vida permite passar deste status para `novo`.
+        pub fn pode_mudar_para(&self, novo: StatusCompromisso) -> bool {
+            use StatusCompromisso::*;
+            matches!(
+                (self, novo),
+                (Agendado, Confirmado | EmAndamento | Concluido | Cancelado)
+                    | (Confirmado, EmAndamento | Concluido | Cancelado)
+                    | (EmAndamento, Concluido | Cancelado)
+            )
+        }
+    }
+
     #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
     #[cfg_attr(
         feature = "std",
@@ -59,608 +306,7752 @@ mod agenda {
         pub hora: String,
         pub prioridade: Prioridade,
         pub duracao: i32,
+        /// Ids dos contatos que participam do compromisso.
+        pub participantes: Vec<u32>,
+        /// Antecedências, em minutos, com que o compromisso deve ser lembrado.
+        pub lembretes: Vec<u32>,
+        pub status: StatusCompromisso,
+        /// Motivo informado na última mudança de status.
+        pub motivo_status: Option<String>,
+        /// Tags normalizadas, em ordem alfabética.
+        pub tags: Vec<String>,
+        pub detalhes: DetalhesCompromisso,
     }
 
-    #[ink(storage)]
-    pub struct Agenda {
-        contatos: Mapping<u32, Contato>,
-        compromissos: Mapping<u32, Compromisso>,
-        next_contato_id: u32,
-        next_compromisso_id: u32,
+    /// Campos opcionais que descrevem um compromisso.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct DetalhesCompromisso {
+        pub descricao: Option<String>,
+        pub local: Option<Local>,
+        /// Endereço da reunião online, começando por `http://` ou `https://`.
+        pub link_reuniao: Option<String>,
     }
 
-    impl Default for Agenda {
-        fn default() -> Self {
-            Self::new()
-        }
+    /// Onde acontece um compromisso: um texto livre, como uma sala ou um
+    /// endereço, e opcionalmente suas coordenadas.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Local {
+        pub texto: String,
+        pub coordenadas: Option<Coordenadas>,
     }
 
-    impl Agenda {
-        #[ink(constructor)]
-        pub fn new() -> Self {
-            Self {
-                contatos: Mapping::default(),
-                compromissos: Mapping::default(),
-                next_contato_id: 0,
-                next_compromisso_id: 0,
-            }
-        }
+    /// Latitude e longitude em milionésimos de grau.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Coordenadas {
+        pub latitude: i32,
+        pub longitude: i32,
+    }
 
-        #[ink(constructor)]
-        pub fn default() -> Self {
-            Self::new()
-        }
+    /// Alteração parcial de um compromisso: apenas os campos preenchidos são
+    /// validados e gravados. Nos campos opcionais do compromisso,
+    /// `Some(None)` apaga o valor atual.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AlteracaoCompromisso {
+        pub titulo: Option<String>,
+        pub data: Option<String>,
+        pub hora: Option<String>,
+        pub prioridade: Option<Prioridade>,
+        pub duracao: Option<i32>,
+        pub descricao: Option<Option<String>>,
+        pub local: Option<Option<Local>>,
+        pub link_reuniao: Option<Option<String>>,
+    }
 
-        // ----- Validações de Inputs -----
+    #[ink(event)]
+    pub struct StatusAlterado {
+        #[ink(topic)]
+        compromisso_id: u32,
+        status: StatusCompromisso,
+    }
 
-        fn validar_data(data: &str) -> bool {
-            let partes: Vec<&str> = data.split('/').collect();
-            if partes.len() != 3 {
-                return false;
-            }
+    /// Lembrete que deve ser entregue por um notificador externo.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct LembretePendente {
+        pub compromisso_id: u32,
+        pub titulo: String,
+        pub antecedencia: u32,
+        /// Momento em que o lembrete deve ser entregue.
+        pub disparo: Timestamp,
+        pub inicio: Timestamp,
+    }
 
-            let dia: u32 = partes[0].parse().unwrap_or(0);
-            let mes: u32 = partes[1].parse().unwrap_or(0);
-            let ano: u32 = partes[2].parse().unwrap_or(0);
+    // ----- Convites -----
 
-            // Validar se a data está no formato dd/mm/aaaa
-            if dia == 0 || mes == 0 || ano == 0 || mes > 12 || dia > 31 {
-                return false;
-            }
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum RespostaConvite {
+        #[default]
+        Pendente,
+        Aceito,
+        Recusado,
+        Talvez,
+    }
 
-            // Validação simples para dias do mês
-            match mes {
-                4 | 6 | 9 | 11 => dia <= 30,
-                2 => {
-                    if ano % 4 == 0 && (ano % 100 != 0 || ano % 400 == 0) {
-                        dia <= 29 // ano bissexto
-                    } else {
-                        dia <= 28
-                    }
-                }
-                _ => dia <= 31,
-            }
-        }
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Convite {
+        pub convidado: AccountId,
+        pub resposta: RespostaConvite,
+    }
 
-        fn validar_hora(hora: &str) -> bool {
-            let partes: Vec<&str> = hora.split(':').collect();
-            if partes.len() != 2 {
-                return false;
-            }
+    /// Convite visto pela conta convidada, junto com o compromisso.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ConviteRecebido {
+        pub compromisso_id: u32,
+        pub compromisso: Compromisso,
+        pub resposta: RespostaConvite,
+    }
 
-            let hora: u32 = partes[0].parse().unwrap_or(0);
-            let minuto: u32 = partes[1].parse().unwrap_or(0);
+    #[ink(event)]
+    pub struct ConviteEnviado {
+        #[ink(topic)]
+        compromisso_id: u32,
+        #[ink(topic)]
+        convidado: AccountId,
+    }
 
-            // Validar se a hora está no formato hh:mm
-            hora < 24 && minuto < 60
-        }
+    #[ink(event)]
+    pub struct ConviteRespondido {
+        #[ink(topic)]
+        compromisso_id: u32,
+        #[ink(topic)]
+        convidado: AccountId,
+        resposta: RespostaConvite,
+    }
 
-        // ----- Métodos para Contatos -----
+    // ----- Reservas -----
 
-        /// Cria um novo contato na agenda.
-        #[ink(message)]
-        pub fn criar_contato(
-            &mut self,
-            nome: String,
-            telefone: String,
-            idade: u32,
-            data_aniversario: String,
-            categoria: Categoria,
-        ) -> Result<u32, String> {
-            if nome.is_empty() {
-                return Err("Nome não pode estar vazio".to_string());
-            }
+    /// Janela de disponibilidade publicada pelo dono da agenda, dividida em
+    /// horários de `duracao_slot` minutos que qualquer conta pode reservar.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Disponibilidade {
+        pub data: String,
+        pub inicio: String,
+        pub fim: String,
+        pub duracao_slot: u32,
+        /// Valor cobrado por horário. Zero para reservas gratuitas.
+        pub preco: Balance,
+    }
 
-            if telefone.is_empty() {
-                return Err("Telefone não pode estar vazio".to_string());
-            }
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum SituacaoReserva {
+        /// Reserva vigente; o valor pago, se houver, está retido no contrato.
+        Ativa,
+        /// O compromisso terminou e o valor foi repassado ao dono.
+        Concluida,
+        Cancelada,
+    }
 
-            if !Self::validar_data(&data_aniversario) {
-                return Err(
-                    "Data de aniversário inválida. O formato deve ser dd/mm/aaaa.".to_string(),
-                );
+    /// Regras de reembolso para reservas pagas canceladas pelo reservante.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PoliticaCancelamento {
+        /// Cancelamentos feitos com pelo menos esta antecedência, em minutos,
+        /// são reembolsados integralmente.
+        pub antecedencia_minima: u32,
+        /// Percentual (0 a 100) reembolsado em cancelamentos mais tardios.
+        pub percentual_tardio: u8,
+    }
+
+    impl Default for PoliticaCancelamento {
+        fn default() -> Self {
+            Self {
+                antecedencia_minima: MINUTOS_POR_DIA as u32,
+                percentual_tardio: 0,
             }
-            let id = self.next_contato_id;
-            let contato = Contato {
-                nome,
-                telefone,
-                idade,
-                data_aniversario,
-                categoria,
-            };
-            self.next_contato_id = self.next_contato_id.checked_add(1).expect("Overflow");
+        }
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Reserva {
+        pub disponibilidade_id: u32,
+        /// Posição do horário reservado dentro da disponibilidade.
+        pub slot: u32,
+        pub compromisso_id: u32,
+        pub reservante: AccountId,
+        /// Valor pago e retido no contrato até o fim do compromisso.
+        pub valor: Balance,
+        pub inicio: Timestamp,
+        pub fim: Timestamp,
+        pub situacao: SituacaoReserva,
+    }
+
+    #[ink(event)]
+    pub struct HorarioReservado {
+        #[ink(topic)]
+        disponibilidade_id: u32,
+        reserva_id: u32,
+        #[ink(topic)]
+        reservante: AccountId,
+    }
+
+    #[ink(event)]
+    pub struct ReservaCancelada {
+        #[ink(topic)]
+        reserva_id: u32,
+        reembolso: Balance,
+    }
+
+    #[ink(event)]
+    pub struct PagamentoLiberado {
+        #[ink(topic)]
+        reserva_id: u32,
+        valor: Balance,
+    }
+
+    /// Critérios combináveis para a busca de compromissos. Campos vazios ou
+    /// `None` não restringem o resultado.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct FiltroCompromissos {
+        /// Primeira data incluída, no formato dd/mm/aaaa.
+        pub data_inicio: Option<String>,
+        /// Última data incluída, no formato dd/mm/aaaa.
+        pub data_fim: Option<String>,
+        pub prioridades: Vec<Prioridade>,
+        /// Trecho que deve aparecer no título, sem diferenciar maiúsculas.
+        pub titulo_contem: Option<String>,
+        /// Id de um contato que deve participar do compromisso.
+        pub participante: Option<u32>,
+        /// Status aceitos. Vazio, aceita todos menos `Cancelado`.
+        pub status: Vec<StatusCompromisso>,
+        /// Tags que o compromisso deve ter, todas elas.
+        pub tags: Vec<String>,
+    }
+
+    /// Uma página do resultado de uma busca de compromissos.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct PaginaCompromissos {
+        pub itens: Vec<(u32, Compromisso)>,
+        /// Total de compromissos que atendem ao filtro, em todas as páginas.
+        pub total: u32,
+    }
+
+    /// Tudo o que acontece em um dia: compromissos ordenados pela hora e os
+    /// contatos que fazem aniversário.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct AgendaDoDia {
+        pub data: String,
+        pub compromissos: Vec<(u32, Compromisso)>,
+        pub aniversariantes: Vec<(u32, Contato)>,
+    }
+
+    // ----- Regras da Agenda -----
+
+    /// O que fazer quando um compromisso viola uma regra da agenda.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum Politica {
+        #[default]
+        Permitir,
+        /// Aceita a operação, mas retorna a violação em `Avisos` e emite um
+        /// evento descrevendo-a.
+        Avisar,
+        Rejeitar,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum DiaSemana {
+        Segunda,
+        Terca,
+        Quarta,
+        Quinta,
+        Sexta,
+        Sabado,
+        Domingo,
+    }
+
+    /// Horário de trabalho de um dia da semana, no formato hh:mm.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Expediente {
+        pub dia: DiaSemana,
+        pub inicio: String,
+        pub fim: String,
+    }
+
+    /// Intervalo de tempo [inicio, fim), em milissegundos desde 01/01/1970.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Intervalo {
+        pub inicio: Timestamp,
+        pub fim: Timestamp,
+    }
+
+    /// Restrições opcionais para a busca de horários livres.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct OpcoesBusca {
+        /// Minutos livres exigidos entre um compromisso e outro.
+        pub intervalo_minimo: u32,
+        /// Quantidade máxima de horários retornados; zero equivale a um.
+        pub max_resultados: u32,
+        /// Considera apenas horários dentro do expediente da agenda.
+        pub apenas_expediente: bool,
+    }
+
+    /// Emitido quando um compromisso é gravado sobrepondo outros e a
+    /// política de conflitos é `Avisar`.
+    #[ink(event)]
+    pub struct ConflitoDetectado {
+        #[ink(topic)]
+        compromisso_id: u32,
+        conflitos: Vec<u32>,
+    }
+
+    /// Emitido quando um compromisso é gravado fora do expediente e a
+    /// política de expediente é `Avisar`.
+    #[ink(event)]
+    pub struct ForaDoExpediente {
+        #[ink(topic)]
+        compromisso_id: u32,
+    }
+
+    /// Avisos das regras da agenda sobre um compromisso gravado, devolvidos
+    /// ao chamador e também emitidos como eventos.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq, Default)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct Avisos {
+        /// Compromissos sobrepostos, quando a política de conflito é `Avisar`.
+        pub conflitos: Vec<u32>,
+        /// Indica se o compromisso ficou fora do expediente, quando a
+        /// política de expediente é `Avisar`.
+        pub fora_do_expediente: bool,
+    }
+
+    // ----- Lotes -----
+
+    /// Resultado de cada item de um lote, na ordem recebida, ou o erro que
+    /// impediu o lote de ser executado.
+    pub type ResultadoLote<T> = Result<Vec<Result<T, String>>, String>;
+
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub enum ModoLote {
+        /// O primeiro erro interrompe o lote e desfaz todas as alterações.
+        #[default]
+        TudoOuNada,
+        /// Os itens com erro são ignorados e os demais são gravados.
+        MelhorEsforco,
+    }
+
+    /// Dados de um contato a ser criado em lote.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct NovoContato {
+        pub nome: String,
+        pub telefone: String,
+        pub idade: u32,
+        pub data_aniversario: String,
+        pub categoria: Categoria,
+    }
+
+    /// Dados de um compromisso a ser criado em lote ou substituído por
+    /// `atualizar_compromisso`.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct NovoCompromisso {
+        pub titulo: String,
+        pub data: String,
+        pub hora: String,
+        pub prioridade: Prioridade,
+        pub duracao: i32,
+        pub detalhes: DetalhesCompromisso,
+    }
+
+    // ----- Lixeira -----
+
+    /// Contato excluído, guardado com os vínculos que tinha para que possa
+    /// ser restaurado.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct ContatoNaLixeira {
+        pub contato: Contato,
+        pub excluido_em: Timestamp,
+        /// Ids dos compromissos de que o contato participava.
+        pub compromissos: Vec<u32>,
+        pub grupos: Vec<u32>,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct CompromissoNaLixeira {
+        pub compromisso: Compromisso,
+        pub excluido_em: Timestamp,
+    }
+
+    /// Posições ocupadas por uma fila da lixeira: de `inicio` a `fim - 1`.
+    #[derive(scale::Encode, scale::Decode, Clone, Copy, Debug, PartialEq, Default)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    struct FilaLixeira {
+        inicio: u32,
+        fim: u32,
+    }
+
+    // ----- Histórico -----
+
+    /// Versão anterior de um contato, com a conta que fez a alteração que a
+    /// substituiu e o momento em que isso aconteceu.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct RevisaoContato {
+        /// Número da revisão, crescente para cada contato.
+        pub numero: u32,
+        pub contato: Contato,
+        pub editor: AccountId,
+        pub editado_em: Timestamp,
+    }
+
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct RevisaoCompromisso {
+        /// Número da revisão, crescente para cada compromisso.
+        pub numero: u32,
+        pub compromisso: Compromisso,
+        pub editor: AccountId,
+        pub editado_em: Timestamp,
+    }
+
+    // ----- Tags -----
+
+    /// Quantidade de registros marcados com uma tag.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct UsoDeTag {
+        pub tag: String,
+        pub contatos: u32,
+        pub compromissos: u32,
+    }
+
+    // ----- Estatísticas -----
+
+    /// Números consolidados da agenda, mantidos incrementalmente pelas
+    /// operações de criação, atualização e exclusão.
+    #[derive(scale::Encode, scale::Decode, Clone, Debug, PartialEq)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink::storage::traits::StorageLayout)
+    )]
+    pub struct Estatisticas {
+        pub total_contatos: u32,
+        pub contatos_por_categoria: Vec<(Categoria, u32)>,
+        pub total_compromissos: u32,
+        pub compromissos_por_prioridade: Vec<(Prioridade, u32)>,
+        pub compromissos_por_status: Vec<(StatusCompromisso, u32)>,
+        /// Compromissos não cancelados do período consultado que começam a
+        /// partir do timestamp do bloco atual.
+        pub compromissos_futuros: u32,
+        /// Compromissos não cancelados do período consultado que começaram
+        /// antes do bloco atual.
+        pub compromissos_passados: u32,
+        /// Minutos agendados nas semanas que tocam o período consultado,
+        /// identificadas pela data da segunda-feira, sem contar os compromissos
+        /// cancelados. Semanas sem minutos agendados são omitidas.
+        pub minutos_por_semana: Vec<(String, u64)>,
+    }
+
+    #[ink(storage)]
+    pub struct Agenda {
+        owner: AccountId,
+        contatos: Mapping<u32, Contato>,
+        compromissos: Mapping<u32, Compromisso>,
+        next_contato_id: u32,
+        next_compromisso_id: u32,
+        /// Ids dos contatos indexados pelo telefone normalizado.
+        contatos_por_telefone: Mapping<String, Vec<u32>>,
+        /// Ids dos contatos indexados pelo aniversário, na forma `mmdd`.
+        contatos_por_aniversario: Mapping<u32, Vec<u32>>,
+        /// Quando ativo, um mesmo telefone não pode pertencer a dois contatos.
+        telefone_unico: bool,
+        total_contatos: u32,
+        contatos_por_categoria: Mapping<Categoria, u32>,
+        /// Categorias personalizadas, além das três padrão.
+        categorias: Mapping<u32, DadosCategoria>,
+        next_categoria_id: u32,
+        grupos: Mapping<u32, Grupo>,
+        next_grupo_id: u32,
+        /// Ids dos grupos de que cada contato faz parte.
+        grupos_por_contato: Mapping<u32, Vec<u32>>,
+        total_compromissos: u32,
+        compromissos_por_prioridade: Mapping<Prioridade, u32>,
+        compromissos_por_status: Mapping<StatusCompromisso, u32>,
+        /// Ids dos compromissos de que cada contato participa.
+        compromissos_por_contato: Mapping<u32, Vec<u32>>,
+        contatos_por_tag: Mapping<String, Vec<u32>>,
+        /// Ids dos compromissos não cancelados marcados com cada tag.
+        compromissos_por_tag: Mapping<String, Vec<u32>>,
+        /// Tags que marcam ao menos um registro, em posições de 0 a
+        /// `total_tags_em_uso - 1`, sem ordem definida.
+        tags_em_uso: Mapping<u32, String>,
+        /// Posição de cada tag em `tags_em_uso`.
+        posicao_tag_em_uso: Mapping<String, u32>,
+        total_tags_em_uso: u32,
+        /// Convites enviados para cada compromisso.
+        convites: Mapping<u32, Vec<Convite>>,
+        /// Ids dos compromissos para os quais cada conta foi convidada.
+        convites_por_conta: Mapping<AccountId, Vec<u32>>,
+        disponibilidades: Mapping<u32, Disponibilidade>,
+        next_disponibilidade_id: u32,
+        /// Posições dos horários bloqueados pelo dono em cada disponibilidade.
+        horarios_bloqueados: Mapping<u32, Vec<u32>>,
+        reservas: Mapping<u32, Reserva>,
+        next_reserva_id: u32,
+        reservas_por_disponibilidade: Mapping<u32, Vec<u32>>,
+        /// Id da reserva que originou cada compromisso reservado.
+        reserva_por_compromisso: Mapping<u32, u32>,
+        /// Ids das reservas feitas por cada conta.
+        reservas_por_conta: Mapping<AccountId, Vec<u32>>,
+        politica_cancelamento: PoliticaCancelamento,
+        /// Antecedências dos lembretes de cada compromisso já entregues.
+        lembretes_confirmados: Mapping<u32, Vec<u32>>,
+        /// Ids dos compromissos indexados pelo dia (em dias desde 01/01/1970).
+        compromissos_por_dia: Mapping<i64, Vec<u32>>,
+        /// Quantidade de compromissos não cancelados em cada dia.
+        ativos_por_dia: Mapping<i64, u32>,
+        /// Dias que possuem ao menos um compromisso, em ordem crescente,
+        /// agrupados em blocos de `DIAS_POR_BLOCO` dias.
+        dias_por_bloco: Mapping<i64, Vec<i64>>,
+        /// Blocos com ao menos um dia em `dias_por_bloco`, em ordem crescente.
+        blocos_com_compromissos: Lazy<Vec<i64>>,
+        /// Minutos agendados em compromissos não cancelados, indexados pela
+        /// segunda-feira de cada semana.
+        minutos_por_semana: Mapping<i64, u64>,
+        /// Quantidade de compromissos não cancelados por recuo, isto é, por
+        /// quantos dias após o de início a duração pode alcançar.
+        ativos_por_recuo: Mapping<i64, u32>,
+        /// Maior recuo em `ativos_por_recuo`, usado para limitar a busca por
+        /// compromissos que começam em dias anteriores e ainda estão em curso.
+        maior_recuo: i64,
+        politica_conflito: Politica,
+        /// Quando desativado, apenas o dono consulta os horários ocupados.
+        disponibilidade_publica: bool,
+        /// Expediente de cada dia útil, ordenado pelo dia da semana. Sem
+        /// nenhum expediente definido, todos os horários são aceitos.
+        expediente: Vec<Expediente>,
+        politica_expediente: Politica,
+        lixeira_contatos: Mapping<u32, ContatoNaLixeira>,
+        lixeira_compromissos: Mapping<u32, CompromissoNaLixeira>,
+        /// Ids dos compromissos na lixeira de que cada contato participa.
+        lixeira_por_participante: Mapping<u32, Vec<u32>>,
+        /// Ids excluídos, na ordem de exclusão, com o momento da exclusão.
+        /// Entradas de registros já restaurados ou apagados ficam obsoletas e
+        /// são ignoradas.
+        contatos_na_lixeira: Mapping<u32, (u32, Timestamp)>,
+        compromissos_na_lixeira: Mapping<u32, (u32, Timestamp)>,
+        fila_contatos_na_lixeira: FilaLixeira,
+        fila_compromissos_na_lixeira: FilaLixeira,
+        /// Dias que um registro excluído fica na lixeira antes de ser apagado.
+        retencao_lixeira: u32,
+        /// Versões anteriores de cada registro, da mais antiga para a mais nova.
+        historico_contatos: Mapping<u32, Vec<RevisaoContato>>,
+        historico_compromissos: Mapping<u32, Vec<RevisaoCompromisso>>,
+        profundidade_historico: u32,
+        /// Maior quantidade de itens aceita numa operação em lote.
+        max_lote: u32,
+    }
+
+    impl Default for Agenda {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Agenda {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                owner: Self::env().caller(),
+                contatos: Mapping::default(),
+                compromissos: Mapping::default(),
+                next_contato_id: 0,
+                next_compromisso_id: 0,
+                contatos_por_telefone: Mapping::default(),
+                contatos_por_aniversario: Mapping::default(),
+                telefone_unico: false,
+                total_contatos: 0,
+                contatos_por_categoria: Mapping::default(),
+                categorias: Mapping::default(),
+                next_categoria_id: 0,
+                grupos: Mapping::default(),
+                next_grupo_id: 0,
+                grupos_por_contato: Mapping::default(),
+                total_compromissos: 0,
+                compromissos_por_prioridade: Mapping::default(),
+                compromissos_por_status: Mapping::default(),
+                compromissos_por_contato: Mapping::default(),
+                contatos_por_tag: Mapping::default(),
+                compromissos_por_tag: Mapping::default(),
+                tags_em_uso: Mapping::default(),
+                posicao_tag_em_uso: Mapping::default(),
+                total_tags_em_uso: 0,
+                convites: Mapping::default(),
+                convites_por_conta: Mapping::default(),
+                disponibilidades: Mapping::default(),
+                next_disponibilidade_id: 0,
+                horarios_bloqueados: Mapping::default(),
+                reservas: Mapping::default(),
+                next_reserva_id: 0,
+                reservas_por_disponibilidade: Mapping::default(),
+                reserva_por_compromisso: Mapping::default(),
+                reservas_por_conta: Mapping::default(),
+                politica_cancelamento: PoliticaCancelamento::default(),
+                lembretes_confirmados: Mapping::default(),
+                compromissos_por_dia: Mapping::default(),
+                ativos_por_dia: Mapping::default(),
+                dias_por_bloco: Mapping::default(),
+                blocos_com_compromissos: Lazy::new(),
+                minutos_por_semana: Mapping::default(),
+                ativos_por_recuo: Mapping::default(),
+                maior_recuo: 0,
+                politica_conflito: Politica::default(),
+                disponibilidade_publica: true,
+                expediente: Vec::new(),
+                politica_expediente: Politica::default(),
+                lixeira_contatos: Mapping::default(),
+                lixeira_compromissos: Mapping::default(),
+                lixeira_por_participante: Mapping::default(),
+                contatos_na_lixeira: Mapping::default(),
+                compromissos_na_lixeira: Mapping::default(),
+                fila_contatos_na_lixeira: FilaLixeira::default(),
+                fila_compromissos_na_lixeira: FilaLixeira::default(),
+                retencao_lixeira: RETENCAO_PADRAO,
+                historico_contatos: Mapping::default(),
+                historico_compromissos: Mapping::default(),
+                profundidade_historico: PROFUNDIDADE_PADRAO,
+                max_lote: LOTE_PADRAO,
+            }
+        }
+
+        #[ink(constructor)]
+        pub fn default() -> Self {
+            Self::new()
+        }
+
+        fn garantir_dono(&self) -> Result<(), String> {
+            if self.env().caller() != self.owner {
+                return Err("Apenas o dono da agenda pode realizar esta operação".to_string());
+            }
+            Ok(())
+        }
+
+        // ----- Validações de Inputs -----
+
+        fn validar_data(data: &str) -> bool {
+            Self::ler_data(data).is_some()
+        }
+
+        /// Converte uma data dd/mm/aaaa em (dia, mês, ano), validando-a.
+        fn ler_data(data: &str) -> Option<(u32, u32, u32)> {
+            let partes: Vec<&str> = data.split('/').collect();
+            if partes.len() != 3 {
+                return None;
+            }
+
+            let dia: u32 = partes[0].parse().unwrap_or(0);
+            let mes: u32 = partes[1].parse().unwrap_or(0);
+            let ano: u32 = partes[2].parse().unwrap_or(0);
+
+            // Validar se a data está no formato dd/mm/aaaa
+            if dia == 0 || mes == 0 || ano == 0 || mes > 12 || dia > 31 {
+                return None;
+            }
+
+            if dia <= Self::dias_no_mes(mes, ano) {
+                Some((dia, mes, ano))
+            } else {
+                None
+            }
+        }
+
+        fn validar_hora(hora: &str) -> bool {
+            Self::ler_hora(hora).is_some()
+        }
+
+        /// Converte uma hora hh:mm em minutos desde a meia-noite, validando-a.
+        fn ler_hora(hora: &str) -> Option<u32> {
+            let partes: Vec<&str> = hora.split(':').collect();
+            if partes.len() != 2 {
+                return None;
+            }
+
+            let hora: u32 = partes[0].parse().unwrap_or(0);
+            let minuto: u32 = partes[1].parse().unwrap_or(0);
+
+            // Validar se a hora está no formato hh:mm
+            if hora < 24 && minuto < 60 {
+                Some(hora * 60 + minuto)
+            } else {
+                None
+            }
+        }
+
+        /// Remove a formatação de um telefone, mantendo apenas os dígitos e um
+        /// eventual `+` inicial. Retorna `None` se não houver nenhum dígito.
+        fn normalizar_telefone(telefone: &str) -> Option<String> {
+            let telefone = telefone.trim();
+            let mut normalizado = String::new();
+            if telefone.starts_with('+') {
+                normalizado.push('+');
+            }
+            normalizado.extend(telefone.chars().filter(|c| c.is_ascii_digit()));
+            if normalizado.trim_start_matches('+').is_empty() {
+                None
+            } else {
+                Some(normalizado)
+            }
+        }
+
+        /// Valida o telefone de um contato e o devolve normalizado.
+        fn validar_telefone(
+            &self,
+            telefone: &str,
+            contato_id: Option<u32>,
+        ) -> Result<String, String> {
+            if telefone.is_empty() {
+                return Err("Telefone não pode estar vazio".to_string());
+            }
+
+            let Some(telefone) = Self::normalizar_telefone(telefone) else {
+                return Err("Telefone inválido. Deve conter ao menos um dígito.".to_string());
+            };
+
+            if self.telefone_unico {
+                let ids = self
+                    .contatos_por_telefone
+                    .get(&telefone)
+                    .unwrap_or_default();
+                if ids.iter().any(|id| Some(*id) != contato_id) {
+                    return Err("Telefone já cadastrado em outro contato".to_string());
+                }
+            }
+            Ok(telefone)
+        }
+
+        /// Valida a sintaxe de um e-mail e o devolve com o domínio em
+        /// minúsculas.
+        fn validar_email(email: &str) -> Result<String, String> {
+            let email = email.trim();
+            let invalido = || "E-mail inválido. O formato deve ser nome@dominio.com.".to_string();
+            if email.len() > MAX_EMAIL {
+                return Err("E-mail muito longo".to_string());
+            }
+            if !email.chars().all(|c| c.is_ascii_graphic()) {
+                return Err(invalido());
+            }
+            let Some((usuario, dominio)) = email.split_once('@') else {
+                return Err(invalido());
+            };
+            if usuario.is_empty()
+                || dominio.contains('@')
+                || !dominio.contains('.')
+                || dominio.split('.').any(|parte| parte.is_empty())
+            {
+                return Err(invalido());
+            }
+            let mut normalizado = String::from(usuario);
+            normalizado.push('@');
+            normalizado.push_str(&dominio.to_ascii_lowercase());
+            Ok(normalizado)
+        }
+
+        fn validar_endereco(endereco: &Endereco) -> Result<(), String> {
+            if endereco.logradouro.trim().is_empty() || endereco.cidade.trim().is_empty() {
+                return Err("Logradouro e cidade não podem estar vazios".to_string());
+            }
+            let campos = [
+                &endereco.logradouro,
+                &endereco.cidade,
+                &endereco.estado,
+                &endereco.cep,
+            ];
+            if campos.iter().any(|campo| campo.len() > MAX_CAMPO_ENDERECO) {
+                return Err("Campo do endereço muito longo".to_string());
+            }
+            Ok(())
+        }
+
+        fn validar_categoria(&self, categoria: &Categoria) -> Result<(), String> {
+            match categoria {
+                Categoria::Personalizada(id) if !self.categorias.contains(id) => {
+                    Err("Categoria não encontrada".to_string())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        /// Valida o nome e a cor de uma categoria. O nome não pode repetir o
+        /// de outra categoria, ignorando maiúsculas e minúsculas.
+        fn validar_dados_categoria(
+            &self,
+            dados: &DadosCategoria,
+            categoria_id: Option<u32>,
+        ) -> Result<(), String> {
+            let nome = dados.nome.trim();
+            if nome.is_empty() {
+                return Err("Nome não pode estar vazio".to_string());
+            }
+            if nome.len() > MAX_NOME_CATEGORIA {
+                return Err("Nome da categoria muito longo".to_string());
+            }
+            let cor = dados.cor.as_bytes();
+            if cor.len() != 7 || cor[0] != b'#' || !cor[1..].iter().all(u8::is_ascii_hexdigit) {
+                return Err("Cor inválida. O formato deve ser #rrggbb.".to_string());
+            }
+            let propria = categoria_id.map(Categoria::Personalizada);
+            let repetido = self.categorias().into_iter().any(|info| {
+                Some(&info.categoria) != propria.as_ref()
+                    && info.nome.to_lowercase() == nome.to_lowercase()
+            });
+            if repetido {
+                return Err("Já existe uma categoria com este nome".to_string());
+            }
+            Ok(())
+        }
+
+        /// Normaliza uma tag para minúsculas. São aceitos apenas letras,
+        /// dígitos, `-` e `_`.
+        fn normalizar_tag(tag: &str) -> Result<String, String> {
+            let tag = tag.trim().to_lowercase();
+            if tag.is_empty() {
+                return Err("Tag não pode estar vazia".to_string());
+            }
+            if tag.len() > MAX_TAG {
+                return Err("Tag muito longa".to_string());
+            }
+            if !tag
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+            {
+                return Err("Tag inválida. Use apenas letras, dígitos, - e _.".to_string());
+            }
+            Ok(tag)
+        }
+
+        /// Insere `tag` na lista ordenada, retornando `false` se ela já existia.
+        fn inserir_tag(tags: &mut Vec<String>, tag: String) -> Result<bool, String> {
+            match tags.binary_search(&tag) {
+                Ok(_) => Ok(false),
+                Err(_) if tags.len() >= MAX_TAGS => Err("Limite de tags atingido".to_string()),
+                Err(pos) => {
+                    tags.insert(pos, tag);
+                    Ok(true)
+                }
+            }
+        }
+
+        fn retirar_tag(tags: &mut Vec<String>, tag: &String) -> bool {
+            match tags.binary_search(tag) {
+                Ok(pos) => {
+                    tags.remove(pos);
+                    true
+                }
+                Err(_) => false,
+            }
+        }
+
+        fn validar_duracao(duracao: i32) -> Result<(), String> {
+            if duracao <= 0 || duracao > MAX_DURACAO {
+                return Err("Duração inválida. Deve estar entre 1 minuto e 7 dias.".to_string());
+            }
+            Ok(())
+        }
+
+        /// Valida os campos opcionais de um compromisso.
+        fn validar_detalhes(detalhes: &DetalhesCompromisso) -> Result<(), String> {
+            let DetalhesCompromisso {
+                descricao,
+                local,
+                link_reuniao,
+            } = detalhes;
+            if descricao.as_ref().is_some_and(|d| d.len() > MAX_DESCRICAO) {
+                return Err("Descrição muito longa".to_string());
+            }
+            if let Some(local) = local {
+                if local.texto.trim().is_empty() {
+                    return Err("Local não pode estar vazio".to_string());
+                }
+                if local.texto.len() > MAX_LOCAL {
+                    return Err("Local muito longo".to_string());
+                }
+                if let Some(Coordenadas {
+                    latitude,
+                    longitude,
+                }) = local.coordenadas
+                {
+                    if latitude.unsigned_abs() > 90_000_000
+                        || longitude.unsigned_abs() > 180_000_000
+                    {
+                        return Err("Coordenadas inválidas".to_string());
+                    }
+                }
+            }
+            if let Some(link) = link_reuniao {
+                if link.len() > MAX_LINK {
+                    return Err("Link da reunião muito longo".to_string());
+                }
+                let endereco = link
+                    .strip_prefix("https://")
+                    .or_else(|| link.strip_prefix("http://"));
+                if !endereco
+                    .is_some_and(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_graphic()))
+                {
+                    return Err(
+                        "Link da reunião inválido. Deve começar com http:// ou https://."
+                            .to_string(),
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        // ----- Listas do contato -----
+
+        /// Acrescenta `entrada` à lista. A primeira entrada de uma lista é
+        /// sempre a principal; marcar outra como principal desmarca a anterior.
+        fn inserir_entrada<T: EntradaDeContato>(
+            lista: &mut Vec<T>,
+            mut entrada: T,
+        ) -> Result<(), String> {
+            if lista.len() >= MAX_ENTRADAS_CONTATO {
+                return Err("Limite de entradas do contato atingido".to_string());
+            }
+            let principal = entrada.principal() || lista.is_empty();
+            if principal {
+                lista.iter_mut().for_each(|e| e.marcar_principal(false));
+            }
+            entrada.marcar_principal(principal);
+            lista.push(entrada);
+            Ok(())
+        }
+
+        /// Remove a entrada na posição `indice`. Se ela era a principal, a
+        /// primeira das restantes passa a ser.
+        fn remover_entrada<T: EntradaDeContato>(
+            lista: &mut Vec<T>,
+            indice: u32,
+        ) -> Result<(), String> {
+            let indice = indice as usize;
+            if indice >= lista.len() {
+                return Err("Entrada não encontrada".to_string());
+            }
+            if lista.remove(indice).principal() {
+                if let Some(primeira) = lista.first_mut() {
+                    primeira.marcar_principal(true);
+                }
+            }
+            Ok(())
+        }
+
+        fn marcar_entrada_principal<T: EntradaDeContato>(
+            lista: &mut [T],
+            indice: u32,
+        ) -> Result<(), String> {
+            let indice = indice as usize;
+            if indice >= lista.len() {
+                return Err("Entrada não encontrada".to_string());
+            }
+            for (i, entrada) in lista.iter_mut().enumerate() {
+                entrada.marcar_principal(i == indice);
+            }
+            Ok(())
+        }
+
+        // ----- Índices -----
+
+        /// Insere `id` na lista ordenada de ids guardada sob `chave`.
+        fn indice_inserir<K, KT>(indice: &mut Mapping<K, Vec<u32>, KT>, chave: &K, id: u32)
+        where
+            K: scale::Encode,
+            KT: StorageKey,
+        {
+            let mut ids = indice.get(chave).unwrap_or_default();
+            if let Err(pos) = ids.binary_search(&id) {
+                ids.insert(pos, id);
+                indice.insert(chave, &ids);
+            }
+        }
+
+        /// Remove `id` da lista de ids guardada sob `chave`.
+        fn indice_remover<K, KT>(indice: &mut Mapping<K, Vec<u32>, KT>, chave: &K, id: u32)
+        where
+            K: scale::Encode,
+            KT: StorageKey,
+        {
+            let mut ids = indice.get(chave).unwrap_or_default();
+            if let Ok(pos) = ids.binary_search(&id) {
+                ids.remove(pos);
+                if ids.is_empty() {
+                    indice.remove(chave);
+                } else {
+                    indice.insert(chave, &ids);
+                }
+            }
+        }
+
+        fn contador_incrementar<K, KT>(contador: &mut Mapping<K, u32, KT>, chave: &K)
+        where
+            K: scale::Encode,
+            KT: StorageKey,
+        {
+            let valor = contador.get(chave).unwrap_or(0);
+            contador.insert(chave, &valor.saturating_add(1));
+        }
+
+        fn contador_decrementar<K, KT>(contador: &mut Mapping<K, u32, KT>, chave: &K)
+        where
+            K: scale::Encode,
+            KT: StorageKey,
+        {
+            match contador.get(chave).unwrap_or(0) {
+                0 | 1 => contador.remove(chave),
+                valor => {
+                    contador.insert(chave, &(valor - 1));
+                }
+            }
+        }
+
+        /// Mantém `tags_em_uso` de acordo com os índices de tags.
+        fn atualizar_tag_em_uso(&mut self, tag: &String) {
+            let em_uso =
+                self.contatos_por_tag.contains(tag) || self.compromissos_por_tag.contains(tag);
+            match (self.posicao_tag_em_uso.get(tag), em_uso) {
+                (None, true) => {
+                    let pos = self.total_tags_em_uso;
+                    self.tags_em_uso.insert(pos, tag);
+                    self.posicao_tag_em_uso.insert(tag, &pos);
+                    self.total_tags_em_uso = pos.saturating_add(1);
+                }
+                (Some(pos), false) => {
+                    // A última tag ocupa a posição liberada
+                    let ultima = self.total_tags_em_uso.saturating_sub(1);
+                    if pos != ultima {
+                        if let Some(movida) = self.tags_em_uso.get(ultima) {
+                            self.tags_em_uso.insert(pos, &movida);
+                            self.posicao_tag_em_uso.insert(&movida, &pos);
+                        }
+                    }
+                    self.tags_em_uso.remove(ultima);
+                    self.posicao_tag_em_uso.remove(tag);
+                    self.total_tags_em_uso = ultima;
+                }
+                _ => {}
+            }
+        }
+
+        fn indexar_contato(&mut self, id: u32, contato: &Contato) {
+            for telefone in &contato.telefones {
+                Self::indice_inserir(&mut self.contatos_por_telefone, &telefone.numero, id);
+            }
+            if let Some((dia, mes, _)) = Self::ler_data(&contato.data_aniversario) {
+                Self::indice_inserir(
+                    &mut self.contatos_por_aniversario,
+                    &Self::chave_aniversario(dia, mes),
+                    id,
+                );
+            }
+            Self::contador_incrementar(&mut self.contatos_por_categoria, &contato.categoria);
+            self.total_contatos = self.total_contatos.saturating_add(1);
+            for tag in &contato.tags {
+                Self::indice_inserir(&mut self.contatos_por_tag, tag, id);
+                self.atualizar_tag_em_uso(tag);
+            }
+        }
+
+        fn desindexar_contato(&mut self, id: u32, contato: &Contato) {
+            for telefone in &contato.telefones {
+                Self::indice_remover(&mut self.contatos_por_telefone, &telefone.numero, id);
+            }
+            if let Some((dia, mes, _)) = Self::ler_data(&contato.data_aniversario) {
+                Self::indice_remover(
+                    &mut self.contatos_por_aniversario,
+                    &Self::chave_aniversario(dia, mes),
+                    id,
+                );
+            }
+            Self::contador_decrementar(&mut self.contatos_por_categoria, &contato.categoria);
+            self.total_contatos = self.total_contatos.saturating_sub(1);
+            for tag in &contato.tags {
+                Self::indice_remover(&mut self.contatos_por_tag, tag, id);
+                self.atualizar_tag_em_uso(tag);
+            }
+        }
+
+        fn indexar_compromisso(&mut self, id: u32, compromisso: &Compromisso) {
+            Self::contador_incrementar(
+                &mut self.compromissos_por_prioridade,
+                &compromisso.prioridade,
+            );
+            Self::contador_incrementar(&mut self.compromissos_por_status, &compromisso.status);
+            self.total_compromissos = self.total_compromissos.saturating_add(1);
+            for contato_id in &compromisso.participantes {
+                Self::indice_inserir(&mut self.compromissos_por_contato, contato_id, id);
+            }
+            // Compromissos cancelados continuam listados por dia, mas não
+            // contam nas tags, nos totais de cada dia nem nos minutos agendados
+            let ativo = compromisso.status != StatusCompromisso::Cancelado;
+            if ativo {
+                for tag in &compromisso.tags {
+                    Self::indice_inserir(&mut self.compromissos_por_tag, tag, id);
+                    self.atualizar_tag_em_uso(tag);
+                }
+            }
+
+            let Some(dia) = Self::dia_do_compromisso(compromisso) else {
+                return;
+            };
+            if !self.compromissos_por_dia.contains(dia) {
+                self.marcar_dia(dia);
+            }
+            Self::indice_inserir(&mut self.compromissos_por_dia, &dia, id);
+            if !ativo {
+                return;
+            }
+            Self::contador_incrementar(&mut self.ativos_por_dia, &dia);
+
+            let recuo = Self::recuo(compromisso);
+            Self::contador_incrementar(&mut self.ativos_por_recuo, &recuo);
+            self.maior_recuo = self.maior_recuo.max(recuo);
+
+            let semana = Self::inicio_da_semana(dia);
+            let minutos = self.minutos_por_semana.get(semana).unwrap_or(0);
+            self.minutos_por_semana.insert(
+                semana,
+                &minutos.saturating_add(Self::duracao_em_minutos(compromisso)),
+            );
+        }
+
+        fn desindexar_compromisso(&mut self, id: u32, compromisso: &Compromisso) {
+            Self::contador_decrementar(
+                &mut self.compromissos_por_prioridade,
+                &compromisso.prioridade,
+            );
+            Self::contador_decrementar(&mut self.compromissos_por_status, &compromisso.status);
+            self.total_compromissos = self.total_compromissos.saturating_sub(1);
+            for contato_id in &compromisso.participantes {
+                Self::indice_remover(&mut self.compromissos_por_contato, contato_id, id);
+            }
+            let ativo = compromisso.status != StatusCompromisso::Cancelado;
+            if ativo {
+                for tag in &compromisso.tags {
+                    Self::indice_remover(&mut self.compromissos_por_tag, tag, id);
+                    self.atualizar_tag_em_uso(tag);
+                }
+            }
+
+            let Some(dia) = Self::dia_do_compromisso(compromisso) else {
+                return;
+            };
+            Self::indice_remover(&mut self.compromissos_por_dia, &dia, id);
+            if !self.compromissos_por_dia.contains(dia) {
+                self.desmarcar_dia(dia);
+            }
+            if !ativo {
+                return;
+            }
+            Self::contador_decrementar(&mut self.ativos_por_dia, &dia);
+
+            let recuo = Self::recuo(compromisso);
+            Self::contador_decrementar(&mut self.ativos_por_recuo, &recuo);
+            if recuo == self.maior_recuo && !self.ativos_por_recuo.contains(recuo) {
+                self.maior_recuo = (0..recuo)
+                    .rev()
+                    .find(|r| self.ativos_por_recuo.contains(r))
+                    .unwrap_or(0);
+            }
+
+            let semana = Self::inicio_da_semana(dia);
+            let minutos = self
+                .minutos_por_semana
+                .get(semana)
+                .unwrap_or(0)
+                .saturating_sub(Self::duracao_em_minutos(compromisso));
+            if minutos == 0 {
+                self.minutos_por_semana.remove(semana);
+            } else {
+                self.minutos_por_semana.insert(semana, &minutos);
+            }
+        }
+
+        /// Inclui o dia no índice de dias com compromissos.
+        fn marcar_dia(&mut self, dia: i64) {
+            let bloco = dia.div_euclid(DIAS_POR_BLOCO);
+            let mut dias = self.dias_por_bloco.get(bloco).unwrap_or_default();
+            if dias.is_empty() {
+                let mut blocos = self.blocos_com_compromissos.get_or_default();
+                if let Err(pos) = blocos.binary_search(&bloco) {
+                    blocos.insert(pos, bloco);
+                    self.blocos_com_compromissos.set(&blocos);
+                }
+            }
+            if let Err(pos) = dias.binary_search(&dia) {
+                dias.insert(pos, dia);
+                self.dias_por_bloco.insert(bloco, &dias);
+            }
+        }
+
+        /// Retira o dia do índice de dias com compromissos.
+        fn desmarcar_dia(&mut self, dia: i64) {
+            let bloco = dia.div_euclid(DIAS_POR_BLOCO);
+            let mut dias = self.dias_por_bloco.get(bloco).unwrap_or_default();
+            let Ok(pos) = dias.binary_search(&dia) else {
+                return;
+            };
+            dias.remove(pos);
+            if !dias.is_empty() {
+                self.dias_por_bloco.insert(bloco, &dias);
+                return;
+            }
+            self.dias_por_bloco.remove(bloco);
+            let mut blocos = self.blocos_com_compromissos.get_or_default();
+            if let Ok(pos) = blocos.binary_search(&bloco) {
+                blocos.remove(pos);
+                self.blocos_com_compromissos.set(&blocos);
+            }
+        }
+
+        /// Dias com compromissos de `primeiro` a `ultimo`, inclusive, em
+        /// ordem crescente.
+        fn dias_com_compromissos(&self, primeiro: i64, ultimo: i64) -> Vec<i64> {
+            let blocos = self.blocos_com_compromissos.get_or_default();
+            let de = blocos.partition_point(|b| *b < primeiro.div_euclid(DIAS_POR_BLOCO));
+            let ate = blocos.partition_point(|b| *b <= ultimo.div_euclid(DIAS_POR_BLOCO));
+            blocos[de..ate.max(de)]
+                .iter()
+                .flat_map(|bloco| self.dias_por_bloco.get(bloco).unwrap_or_default())
+                .filter(|dia| primeiro <= *dia && *dia <= ultimo)
+                .collect()
+        }
+
+        // ----- Conflitos -----
+
+        /// Compromissos que ocupam algum momento do intervalo [inicio, fim),
+        /// em minutos desde 01/01/1970, como (id, início, fim). Um compromisso
+        /// que começa exatamente em `inicio` sempre é incluído, mesmo sem
+        /// duração.
+        fn ocupacoes(&self, inicio: i64, fim: i64) -> Vec<(u32, i64, i64)> {
+            let primeiro_dia = inicio.div_euclid(MINUTOS_POR_DIA) - self.maior_recuo;
+            let ultimo_dia = fim.max(inicio).div_euclid(MINUTOS_POR_DIA);
+
+            let mut ocupacoes = Vec::new();
+            for dia in self.dias_com_compromissos(primeiro_dia, ultimo_dia) {
+                for id in self.compromissos_por_dia.get(dia).unwrap_or_default() {
+                    let Some(compromisso) = self.compromissos.get(id) else {
+                        continue;
+                    };
+                    if compromisso.status == StatusCompromisso::Cancelado {
+                        continue;
+                    }
+                    let Some(c_inicio) = Self::inicio_do_compromisso(&compromisso) else {
+                        continue;
+                    };
+                    let c_fim = c_inicio + Self::duracao_em_minutos(&compromisso) as i64;
+                    if c_inicio == inicio || (c_inicio < fim && inicio < c_fim) {
+                        ocupacoes.push((id, c_inicio, c_fim));
+                    }
+                }
+            }
+            ocupacoes.sort_by_key(|(id, c_inicio, _)| (*c_inicio, *id));
+            ocupacoes
+        }
+
+        /// Ids dos compromissos que se sobrepõem ao horário informado.
+        fn conflitos(
+            &self,
+            data: &str,
+            hora: &str,
+            duracao: i32,
+            ignorar: Option<u32>,
+        ) -> Vec<u32> {
+            let (Some((d, m, a)), Some(minuto)) = (Self::ler_data(data), Self::ler_hora(hora))
+            else {
+                return Vec::new();
+            };
+            let inicio = Self::dias_desde_epoch(d, m, a) * MINUTOS_POR_DIA + minuto as i64;
+            let fim = inicio + duracao.max(0) as i64;
+            self.ocupacoes(inicio, fim)
+                .into_iter()
+                .map(|(id, _, _)| id)
+                .filter(|id| Some(*id) != ignorar)
+                .collect()
+        }
+
+        /// Aplica a política de conflitos, retornando os conflitos que devem
+        /// ser avisados depois que o compromisso for gravado.
+        fn aplicar_politica_conflito(
+            &self,
+            data: &str,
+            hora: &str,
+            duracao: i32,
+            ignorar: Option<u32>,
+        ) -> Result<Vec<u32>, String> {
+            if self.politica_conflito == Politica::Permitir {
+                return Ok(Vec::new());
+            }
+            let conflitos = self.conflitos(data, hora, duracao, ignorar);
+            if self.politica_conflito == Politica::Rejeitar && !conflitos.is_empty() {
+                return Err(ink::prelude::format!(
+                    "Conflito de horário com o(s) compromisso(s) {:?}",
+                    conflitos
+                ));
+            }
+            Ok(conflitos)
+        }
+
+        // ----- Expediente -----
+
+        /// Expediente do dia (em dias desde 01/01/1970), em minutos desde o
+        /// início desse dia. `None` se o dia não tiver expediente.
+        fn expediente_do_dia(&self, dia: i64) -> Option<(i64, i64)> {
+            let dia_semana = Self::dia_da_semana(dia);
+            let expediente = self.expediente.iter().find(|e| e.dia == dia_semana)?;
+            let inicio = Self::ler_hora(&expediente.inicio)? as i64;
+            let fim = Self::ler_hora(&expediente.fim)? as i64;
+            Some((dia * MINUTOS_POR_DIA + inicio, dia * MINUTOS_POR_DIA + fim))
+        }
+
+        /// Indica se o intervalo [inicio, fim), em minutos desde 01/01/1970,
+        /// cabe inteiro no expediente do dia em que começa.
+        fn dentro_do_expediente(&self, inicio: i64, fim: i64) -> bool {
+            if self.expediente.is_empty() {
+                return true;
+            }
+            match self.expediente_do_dia(inicio.div_euclid(MINUTOS_POR_DIA)) {
+                Some((de, ate)) => de <= inicio && fim <= ate,
+                None => false,
+            }
+        }
+
+        /// Recorta [inicio, fim) aos trechos que estão dentro do expediente.
+        fn trechos_no_expediente(&self, inicio: i64, fim: i64) -> Vec<(i64, i64)> {
+            if self.expediente.is_empty() {
+                return ink::prelude::vec![(inicio, fim)];
+            }
+            let mut trechos = Vec::new();
+            for dia in inicio.div_euclid(MINUTOS_POR_DIA)..=(fim - 1).div_euclid(MINUTOS_POR_DIA) {
+                if let Some((de, ate)) = self.expediente_do_dia(dia) {
+                    let (de, ate) = (de.max(inicio), ate.min(fim));
+                    if de < ate {
+                        trechos.push((de, ate));
+                    }
+                }
+            }
+            trechos
+        }
+
+        /// Aplica a política de expediente, indicando se o compromisso deve
+        /// ser avisado como fora do expediente depois de gravado.
+        fn aplicar_politica_expediente(
+            &self,
+            data: &str,
+            hora: &str,
+            duracao: i32,
+        ) -> Result<bool, String> {
+            if self.politica_expediente == Politica::Permitir {
+                return Ok(false);
+            }
+            let (Some((d, m, a)), Some(minuto)) = (Self::ler_data(data), Self::ler_hora(hora))
+            else {
+                return Ok(false);
+            };
+            let inicio = Self::dias_desde_epoch(d, m, a) * MINUTOS_POR_DIA + minuto as i64;
+            if self.dentro_do_expediente(inicio, inicio + duracao.max(0) as i64) {
+                return Ok(false);
+            }
+            if self.politica_expediente == Politica::Rejeitar {
+                return Err("Compromisso fora do expediente da agenda".to_string());
+            }
+            Ok(true)
+        }
+
+        /// Aplica todas as regras da agenda a um horário, retornando os
+        /// avisos a emitir depois que o compromisso for gravado.
+        fn aplicar_regras(
+            &self,
+            data: &str,
+            hora: &str,
+            duracao: i32,
+            ignorar: Option<u32>,
+        ) -> Result<Avisos, String> {
+            Ok(Avisos {
+                conflitos: self.aplicar_politica_conflito(data, hora, duracao, ignorar)?,
+                fora_do_expediente: self.aplicar_politica_expediente(data, hora, duracao)?,
+            })
+        }
+
+        fn emitir_avisos(&self, compromisso_id: u32, avisos: &Avisos) {
+            if !avisos.conflitos.is_empty() {
+                self.env().emit_event(ConflitoDetectado {
+                    compromisso_id,
+                    conflitos: avisos.conflitos.clone(),
+                });
+            }
+            if avisos.fora_do_expediente {
+                self.env().emit_event(ForaDoExpediente { compromisso_id });
+            }
+        }
+
+        /// Intervalos ocupados dentro de [inicio, fim), já mesclados e
+        /// recortados aos limites da consulta.
+        fn intervalos_ocupados(&self, inicio: Timestamp, fim: Timestamp) -> Vec<Intervalo> {
+            let inicio_min = (inicio / MS_POR_MINUTO) as i64;
+            let fim_min = fim.div_ceil(MS_POR_MINUTO) as i64;
+
+            let mut ocupados: Vec<Intervalo> = Vec::new();
+            for (_, c_inicio, c_fim) in self.ocupacoes(inicio_min, fim_min) {
+                let c_inicio = (c_inicio.max(0) as u64 * MS_POR_MINUTO).max(inicio);
+                let c_fim = (c_fim.max(0) as u64 * MS_POR_MINUTO).min(fim);
+                if c_inicio >= c_fim {
+                    continue;
+                }
+                match ocupados.last_mut() {
+                    Some(ultimo) if c_inicio <= ultimo.fim => ultimo.fim = ultimo.fim.max(c_fim),
+                    _ => ocupados.push(Intervalo {
+                        inicio: c_inicio,
+                        fim: c_fim,
+                    }),
+                }
+            }
+            ocupados
+        }
+
+        /// Horários livres de `duracao` minutos dentro de [inicio, fim), em
+        /// minutos desde 01/01/1970, do mais cedo para o mais tarde.
+        fn horarios_livres(
+            &self,
+            duracao: i64,
+            inicio: i64,
+            fim: i64,
+            opcoes: &OpcoesBusca,
+        ) -> Vec<(i64, i64)> {
+            let folga = opcoes.intervalo_minimo as i64;
+            let limite = opcoes.max_resultados.clamp(1, MAX_POR_PAGINA) as usize;
+
+            let mut livres = Vec::new();
+            let preencher = |de: i64, ate: i64, livres: &mut Vec<(i64, i64)>| {
+                let trechos = if opcoes.apenas_expediente {
+                    self.trechos_no_expediente(de, ate)
+                } else {
+                    ink::prelude::vec![(de, ate)]
+                };
+                for (mut de, ate) in trechos {
+                    while de + duracao <= ate && livres.len() < limite {
+                        livres.push((de, de + duracao));
+                        de += duracao + folga;
+                    }
+                }
+            };
+
+            let mut cursor = inicio;
+            for (_, c_inicio, c_fim) in self.ocupacoes(inicio - folga, fim + folga) {
+                let (ocupado_de, ocupado_ate) = (c_inicio - folga, c_fim + folga);
+                if ocupado_de >= ocupado_ate {
+                    continue;
+                }
+                preencher(cursor, ocupado_de.min(fim), &mut livres);
+                cursor = cursor.max(ocupado_ate);
+                if cursor >= fim {
+                    break;
+                }
+            }
+            preencher(cursor, fim, &mut livres);
+            livres
+        }
+
+        fn validar_consulta(inicio: Timestamp, fim: Timestamp) -> Result<(), String> {
+            if fim <= inicio {
+                return Err("O fim do intervalo deve ser posterior ao início".to_string());
+            }
+            if fim - inicio > MAX_DIAS_CONSULTA * MS_POR_DIA {
+                return Err("Intervalo de consulta muito longo".to_string());
+            }
+            Ok(())
+        }
+
+        // ----- Utilidades de Data -----
+
+        fn ano_bissexto(ano: u32) -> bool {
+            ano.is_multiple_of(4) && (!ano.is_multiple_of(100) || ano.is_multiple_of(400))
+        }
+
+        fn dias_no_mes(mes: u32, ano: u32) -> u32 {
+            // Validação simples para dias do mês
+            match mes {
+                4 | 6 | 9 | 11 => 30,
+                2 => {
+                    if Self::ano_bissexto(ano) {
+                        29 // ano bissexto
+                    } else {
+                        28
+                    }
+                }
+                _ => 31,
+            }
+        }
+
+        /// Número de dias entre 01/01/1970 e a data informada.
+        fn dias_desde_epoch(dia: u32, mes: u32, ano: u32) -> i64 {
+            let ano = if mes <= 2 { ano as i64 - 1 } else { ano as i64 };
+            let era = ano.div_euclid(400);
+            let ano_da_era = ano - era * 400;
+            let mes = mes as i64;
+            let dia_do_ano =
+                (153 * (if mes > 2 { mes - 3 } else { mes + 9 }) + 2) / 5 + dia as i64 - 1;
+            let dia_da_era = ano_da_era * 365 + ano_da_era / 4 - ano_da_era / 100 + dia_do_ano;
+            era * 146_097 + dia_da_era - 719_468
+        }
+
+        /// Inverso de `dias_desde_epoch`: devolve (dia, mês, ano).
+        fn data_de_dias(dias: i64) -> (u32, u32, u32) {
+            let z = dias + 719_468;
+            let era = z.div_euclid(146_097);
+            let dia_da_era = z - era * 146_097;
+            let ano_da_era =
+                (dia_da_era - dia_da_era / 1460 + dia_da_era / 36_524 - dia_da_era / 146_096) / 365;
+            let dia_do_ano = dia_da_era - (365 * ano_da_era + ano_da_era / 4 - ano_da_era / 100);
+            let mp = (5 * dia_do_ano + 2) / 153;
+            let dia = dia_do_ano - (153 * mp + 2) / 5 + 1;
+            let mes = if mp < 10 { mp + 3 } else { mp - 9 };
+            let ano = ano_da_era + era * 400 + if mes <= 2 { 1 } else { 0 };
+            (dia as u32, mes as u32, ano as u32)
+        }
+
+        /// Segunda-feira da semana que contém o dia informado. 01/01/1970 foi
+        /// uma quinta-feira.
+        fn inicio_da_semana(dia: i64) -> i64 {
+            dia - (dia + 3).rem_euclid(7)
+        }
+
+        fn dia_da_semana(dia: i64) -> DiaSemana {
+            const DIAS: [DiaSemana; 7] = [
+                DiaSemana::Segunda,
+                DiaSemana::Terca,
+                DiaSemana::Quarta,
+                DiaSemana::Quinta,
+                DiaSemana::Sexta,
+                DiaSemana::Sabado,
+                DiaSemana::Domingo,
+            ];
+            DIAS[(dia + 3).rem_euclid(7) as usize]
+        }
+
+        fn dia_do_compromisso(compromisso: &Compromisso) -> Option<i64> {
+            let (dia, mes, ano) = Self::ler_data(&compromisso.data)?;
+            Some(Self::dias_desde_epoch(dia, mes, ano))
+        }
+
+        /// Início do compromisso em minutos desde 01/01/1970.
+        fn inicio_do_compromisso(compromisso: &Compromisso) -> Option<i64> {
+            let dia = Self::dia_do_compromisso(compromisso)?;
+            let hora = Self::ler_hora(&compromisso.hora)?;
+            Some(dia * MINUTOS_POR_DIA + hora as i64)
+        }
+
+        fn duracao_em_minutos(compromisso: &Compromisso) -> u64 {
+            compromisso.duracao.max(0) as u64
+        }
+
+        /// Quantos dias após o de início o compromisso pode alcançar.
+        fn recuo(compromisso: &Compromisso) -> i64 {
+            Self::duracao_em_minutos(compromisso).div_ceil(MINUTOS_POR_DIA as u64) as i64
+        }
+
+        fn formatar_data(dia: u32, mes: u32, ano: u32) -> String {
+            ink::prelude::format!("{:02}/{:02}/{:04}", dia, mes, ano)
+        }
+
+        /// Dia atual (em dias desde 01/01/1970) segundo o timestamp do bloco.
+        fn hoje(&self) -> i64 {
+            (self.env().block_timestamp() / MS_POR_DIA) as i64
+        }
+
+        fn chave_aniversario(dia: u32, mes: u32) -> u32 {
+            mes * 100 + dia
+        }
+
+        /// Data em que um aniversário cai no ano informado. Quem nasceu em
+        /// 29/02 comemora em 28/02 nos anos não bissextos.
+        fn aniversario_no_ano(dia: u32, mes: u32, ano: u32) -> (u32, u32, u32) {
+            if mes == 2 && dia == 29 && !Self::ano_bissexto(ano) {
+                (28, 2, ano)
+            } else {
+                (dia, mes, ano)
+            }
+        }
+
+        // ----- Métodos para Contatos -----
+
+        /// Cria um novo contato na agenda.
+        #[ink(message)]
+        pub fn criar_contato(
+            &mut self,
+            nome: String,
+            telefone: String,
+            idade: u32,
+            data_aniversario: String,
+            categoria: Categoria,
+        ) -> Result<u32, String> {
+            if nome.is_empty() {
+                return Err("Nome não pode estar vazio".to_string());
+            }
+
+            let telefone = self.validar_telefone(&telefone, None)?;
+            self.validar_categoria(&categoria)?;
+
+            if !Self::validar_data(&data_aniversario) {
+                return Err(
+                    "Data de aniversário inválida. O formato deve ser dd/mm/aaaa.".to_string(),
+                );
+            }
+            let id = self.next_contato_id;
+            let contato = Contato {
+                nome,
+                telefones: [Telefone {
+                    rotulo: Rotulo::Celular,
+                    numero: telefone.clone(),
+                    principal: true,
+                }]
+                .to_vec(),
+                telefone,
+                idade,
+                data_aniversario,
+                categoria,
+                emails: Vec::new(),
+                enderecos: Vec::new(),
+                tags: Vec::new(),
+            };
+            self.next_contato_id = self.next_contato_id.checked_add(1).expect("Overflow");
+            self.contatos.insert(id, &contato);
+            self.indexar_contato(id, &contato);
+            Ok(id)
+        }
+
+        /// Lê um contato da agenda.
+        #[ink(message)]
+        pub fn ler_contato(&self, id: u32) -> Option<Contato> {
+            self.contatos.get(id)
+        }
+
+        /// Atualiza um contato da agenda.
+        #[ink(message)]
+        pub fn atualizar_contato(
+            &mut self,
+            id: u32,
+            nome: String,
+            telefone: String,
+            idade: u32,
+            data_aniversario: String,
+            categoria: Categoria,
+        ) -> Result<bool, String> {
+            if nome.is_empty() {
+                return Err("Nome não pode estar vazio".to_string());
+            }
+
+            let telefone = self.validar_telefone(&telefone, Some(id))?;
+            self.validar_categoria(&categoria)?;
+
+            if !Self::validar_data(&data_aniversario) {
+                return Err(
+                    "Data de aniversário inválida. O formato deve ser dd/mm/aaaa.".to_string(),
+                );
+            }
+
+            if let Some(antigo) = self.contatos.get(id) {
+                let mut contato = antigo.clone();
+                contato.nome = nome;
+                Self::substituir_telefone_principal(&mut contato, telefone);
+                contato.idade = idade;
+                contato.data_aniversario = data_aniversario;
+                contato.categoria = categoria;
+                self.gravar_contato(id, &antigo, &contato);
+                Ok(true)
+            } else {
+                Err("Contato não encontrado".to_string())
+            }
+        }
+
+        /// Troca o número do telefone principal, descartando uma eventual
+        /// entrada repetida com o mesmo número.
+        fn substituir_telefone_principal(contato: &mut Contato, telefone: String) {
+            match contato.telefones.iter_mut().find(|t| t.principal) {
+                Some(principal) => principal.numero = telefone.clone(),
+                None => contato.telefones.push(Telefone {
+                    rotulo: Rotulo::Celular,
+                    numero: telefone.clone(),
+                    principal: true,
+                }),
+            }
+            contato
+                .telefones
+                .retain(|t| t.principal || t.numero != telefone);
+            contato.telefone = telefone;
+        }
+
+        /// Atualiza apenas os campos preenchidos em `alteracao`.
+        #[ink(message)]
+        pub fn atualizar_contato_parcial(
+            &mut self,
+            id: u32,
+            alteracao: AlteracaoContato,
+        ) -> Result<(), String> {
+            if alteracao.nome.as_ref().is_some_and(|nome| nome.is_empty()) {
+                return Err("Nome não pode estar vazio".to_string());
+            }
+            let telefone = alteracao
+                .telefone
+                .as_deref()
+                .map(|telefone| self.validar_telefone(telefone, Some(id)))
+                .transpose()?;
+            if alteracao
+                .data_aniversario
+                .as_ref()
+                .is_some_and(|data| !Self::validar_data(data))
+            {
+                return Err(
+                    "Data de aniversário inválida. O formato deve ser dd/mm/aaaa.".to_string(),
+                );
+            }
+            if let Some(categoria) = &alteracao.categoria {
+                self.validar_categoria(categoria)?;
+            }
+
+            self.alterar_contato(id, |contato| {
+                if let Some(nome) = alteracao.nome {
+                    contato.nome = nome;
+                }
+                if let Some(telefone) = telefone {
+                    Self::substituir_telefone_principal(contato, telefone);
+                }
+                if let Some(idade) = alteracao.idade {
+                    contato.idade = idade;
+                }
+                if let Some(data) = alteracao.data_aniversario {
+                    contato.data_aniversario = data;
+                }
+                if let Some(categoria) = alteracao.categoria {
+                    contato.categoria = categoria;
+                }
+                Ok(())
+            })
+        }
+
+        /// Regrava um contato alterado, atualizando seus índices.
+        fn gravar_contato(&mut self, id: u32, antigo: &Contato, novo: &Contato) {
+            self.desindexar_contato(id, antigo);
+            self.contatos.insert(id, novo);
+            self.indexar_contato(id, novo);
+
+            let mut historico = self.historico_contatos.get(id).unwrap_or_default();
+            let numero = historico.last().map_or(1, |r| r.numero.saturating_add(1));
+            historico.push(RevisaoContato {
+                numero,
+                contato: antigo.clone(),
+                editor: self.env().caller(),
+                editado_em: self.env().block_timestamp(),
+            });
+            self.guardar_historico(&mut historico);
+            if historico.is_empty() {
+                self.historico_contatos.remove(id);
+            } else {
+                self.historico_contatos.insert(id, &historico);
+            }
+        }
+
+        /// Aplica `alteracao` a uma cópia do contato e grava o resultado,
+        /// mantendo o campo `telefone` igual ao telefone principal.
+        fn alterar_contato<F>(&mut self, id: u32, alteracao: F) -> Result<(), String>
+        where
+            F: FnOnce(&mut Contato) -> Result<(), String>,
+        {
+            let antigo = self
+                .contatos
+                .get(id)
+                .ok_or_else(|| "Contato não encontrado".to_string())?;
+            let mut novo = antigo.clone();
+            alteracao(&mut novo)?;
+            if let Some(principal) = novo.telefones.iter().find(|t| t.principal) {
+                novo.telefone = principal.numero.clone();
+            }
+            self.gravar_contato(id, &antigo, &novo);
+            Ok(())
+        }
+
+        /// Adiciona um telefone ao contato. Se `principal` estiver marcado, ele
+        /// passa a ser o telefone principal.
+        #[ink(message)]
+        pub fn adicionar_telefone(
+            &mut self,
+            contato_id: u32,
+            mut telefone: Telefone,
+        ) -> Result<(), String> {
+            telefone.numero = self.validar_telefone(&telefone.numero, Some(contato_id))?;
+            self.alterar_contato(contato_id, |contato| {
+                if contato
+                    .telefones
+                    .iter()
+                    .any(|t| t.numero == telefone.numero)
+                {
+                    return Err("Telefone já cadastrado neste contato".to_string());
+                }
+                Self::inserir_entrada(&mut contato.telefones, telefone)
+            })
+        }
+
+        /// Remove o telefone na posição `indice`. O contato precisa manter ao
+        /// menos um telefone.
+        #[ink(message)]
+        pub fn remover_telefone(&mut self, contato_id: u32, indice: u32) -> Result<(), String> {
+            self.alterar_contato(contato_id, |contato| {
+                if contato.telefones.len() == 1 && indice == 0 {
+                    return Err("O contato precisa de ao menos um telefone".to_string());
+                }
+                Self::remover_entrada(&mut contato.telefones, indice)
+            })
+        }
+
+        #[ink(message)]
+        pub fn definir_telefone_principal(
+            &mut self,
+            contato_id: u32,
+            indice: u32,
+        ) -> Result<(), String> {
+            self.alterar_contato(contato_id, |contato| {
+                Self::marcar_entrada_principal(&mut contato.telefones, indice)
+            })
+        }
+
+        /// Adiciona um e-mail ao contato, validando sua sintaxe.
+        #[ink(message)]
+        pub fn adicionar_email(&mut self, contato_id: u32, mut email: Email) -> Result<(), String> {
+            email.endereco = Self::validar_email(&email.endereco)?;
+            self.alterar_contato(contato_id, |contato| {
+                if contato
+                    .emails
+                    .iter()
+                    .any(|e| e.endereco.eq_ignore_ascii_case(&email.endereco))
+                {
+                    return Err("E-mail já cadastrado neste contato".to_string());
+                }
+                Self::inserir_entrada(&mut contato.emails, email)
+            })
+        }
+
+        #[ink(message)]
+        pub fn remover_email(&mut self, contato_id: u32, indice: u32) -> Result<(), String> {
+            self.alterar_contato(contato_id, |contato| {
+                Self::remover_entrada(&mut contato.emails, indice)
+            })
+        }
+
+        #[ink(message)]
+        pub fn definir_email_principal(
+            &mut self,
+            contato_id: u32,
+            indice: u32,
+        ) -> Result<(), String> {
+            self.alterar_contato(contato_id, |contato| {
+                Self::marcar_entrada_principal(&mut contato.emails, indice)
+            })
+        }
+
+        /// Adiciona um endereço postal ao contato.
+        #[ink(message)]
+        pub fn adicionar_endereco(
+            &mut self,
+            contato_id: u32,
+            endereco: Endereco,
+        ) -> Result<(), String> {
+            Self::validar_endereco(&endereco)?;
+            self.alterar_contato(contato_id, |contato| {
+                Self::inserir_entrada(&mut contato.enderecos, endereco)
+            })
+        }
+
+        #[ink(message)]
+        pub fn remover_endereco(&mut self, contato_id: u32, indice: u32) -> Result<(), String> {
+            self.alterar_contato(contato_id, |contato| {
+                Self::remover_entrada(&mut contato.enderecos, indice)
+            })
+        }
+
+        #[ink(message)]
+        pub fn definir_endereco_principal(
+            &mut self,
+            contato_id: u32,
+            indice: u32,
+        ) -> Result<(), String> {
+            self.alterar_contato(contato_id, |contato| {
+                Self::marcar_entrada_principal(&mut contato.enderecos, indice)
+            })
+        }
+
+        /// Deleta um contato da agenda, movendo-o para a lixeira.
+        #[ink(message)]
+        pub fn deletar_contato(&mut self, id: u32) -> bool {
+            if let Some(contato) = self.contatos.get(id) {
+                self.purgar_lixeira_expirada();
+                let compromissos = self.compromissos_por_contato.get(id).unwrap_or_default();
+                let grupos = self.grupos_por_contato.get(id).unwrap_or_default();
+                self.desindexar_contato(id, &contato);
+                self.contatos.remove(id);
+                self.remover_participacoes(id);
+                self.remover_dos_grupos(id);
+                let excluido_em = self.env().block_timestamp();
+                self.lixeira_contatos.insert(
+                    id,
+                    &ContatoNaLixeira {
+                        contato,
+                        excluido_em,
+                        compromissos,
+                        grupos,
+                    },
+                );
+                Self::enfileirar(
+                    &mut self.contatos_na_lixeira,
+                    &mut self.fila_contatos_na_lixeira,
+                    id,
+                    excluido_em,
+                );
+                true
+            } else {
+                false
+            }
+        }
+
+        /// Busca os contatos que possuem o telefone informado. O telefone é
+        /// normalizado antes da busca, então a formatação não importa.
+        #[ink(message)]
+        pub fn contato_por_telefone(&self, telefone: String) -> Vec<(u32, Contato)> {
+            let Some(telefone) = Self::normalizar_telefone(&telefone) else {
+                return Vec::new();
+            };
+            self.contatos_por_telefone
+                .get(&telefone)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.contatos.get(id).map(|contato| (id, contato)))
+                .collect()
+        }
+
+        /// Define se a agenda deve rejeitar telefones já cadastrados em outro
+        /// contato. Vale para as próximas criações e atualizações.
+        #[ink(message)]
+        pub fn definir_telefone_unico(&mut self, ativo: bool) -> Result<(), String> {
+            self.garantir_dono()?;
+            self.telefone_unico = ativo;
+            Ok(())
+        }
+
+        /// Lista todos os contatos da agenda.
+        #[ink(message)]
+        pub fn listar_contatos(&self) -> Vec<Contato> {
+            let mut lista = Vec::new();
+            for id in 0..self.next_contato_id {
+                if let Some(contato) = self.contatos.get(id) {
+                    lista.push(contato);
+                }
+            }
+            lista
+        }
+
+        // ----- Categorias -----
+
+        /// Cria uma categoria personalizada para os contatos.
+        #[ink(message)]
+        pub fn criar_categoria(&mut self, nome: String, cor: String) -> Result<Categoria, String> {
+            self.garantir_dono()?;
+            let dados = DadosCategoria {
+                nome: nome.trim().to_string(),
+                cor: cor.to_lowercase(),
+            };
+            self.validar_dados_categoria(&dados, None)?;
+            let id = self.next_categoria_id;
+            self.next_categoria_id = self.next_categoria_id.checked_add(1).expect("Overflow");
+            self.categorias.insert(id, &dados);
+            Ok(Categoria::Personalizada(id))
+        }
+
+        /// Altera o nome e a cor de uma categoria personalizada.
+        #[ink(message)]
+        pub fn atualizar_categoria(
+            &mut self,
+            id: u32,
+            nome: String,
+            cor: String,
+        ) -> Result<(), String> {
+            self.garantir_dono()?;
+            if !self.categorias.contains(id) {
+                return Err("Categoria não encontrada".to_string());
+            }
+            let dados = DadosCategoria {
+                nome: nome.trim().to_string(),
+                cor: cor.to_lowercase(),
+            };
+            self.validar_dados_categoria(&dados, Some(id))?;
+            self.categorias.insert(id, &dados);
+            Ok(())
+        }
+
+        /// Remove uma categoria personalizada. Categorias ainda usadas por
+        /// algum contato não podem ser removidas.
+        #[ink(message)]
+        pub fn remover_categoria(&mut self, id: u32) -> Result<bool, String> {
+            self.garantir_dono()?;
+            if !self.categorias.contains(id) {
+                return Ok(false);
+            }
+            let em_uso = self
+                .contatos_por_categoria
+                .get(Categoria::Personalizada(id))
+                .unwrap_or(0);
+            if em_uso > 0 {
+                return Err("Categoria em uso por algum contato".to_string());
+            }
+            self.categorias.remove(id);
+            Ok(true)
+        }
+
+        /// Lista as categorias disponíveis: as três padrão seguidas das
+        /// personalizadas.
+        #[ink(message)]
+        pub fn listar_categorias(&self) -> Vec<InfoCategoria> {
+            self.categorias()
+        }
+
+        fn categorias(&self) -> Vec<InfoCategoria> {
+            let padrao = [
+                ("Amigo", "#2e7d32"),
+                ("Familiar", "#c62828"),
+                ("Colega", "#1565c0"),
+            ];
+            let mut lista: Vec<InfoCategoria> = Categoria::PADRAO
+                .into_iter()
+                .zip(padrao)
+                .map(|(categoria, (nome, cor))| InfoCategoria {
+                    categoria,
+                    nome: nome.to_string(),
+                    cor: cor.to_string(),
+                })
+                .collect();
+            for id in 0..self.next_categoria_id {
+                if let Some(dados) = self.categorias.get(id) {
+                    lista.push(InfoCategoria {
+                        categoria: Categoria::Personalizada(id),
+                        nome: dados.nome,
+                        cor: dados.cor,
+                    });
+                }
+            }
+            lista
+        }
+
+        // ----- Grupos -----
+
+        fn validar_nome_grupo(nome: &str) -> Result<(), String> {
+            if nome.trim().is_empty() {
+                return Err("Nome não pode estar vazio".to_string());
+            }
+            if nome.len() > MAX_NOME_GRUPO {
+                return Err("Nome do grupo muito longo".to_string());
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn criar_grupo(&mut self, nome: String) -> Result<u32, String> {
+            Self::validar_nome_grupo(&nome)?;
+            let id = self.next_grupo_id;
+            self.next_grupo_id = self.next_grupo_id.checked_add(1).expect("Overflow");
+            self.grupos.insert(
+                id,
+                &Grupo {
+                    nome,
+                    membros: Vec::new(),
+                },
+            );
+            Ok(id)
+        }
+
+        #[ink(message)]
+        pub fn renomear_grupo(&mut self, id: u32, nome: String) -> Result<(), String> {
+            Self::validar_nome_grupo(&nome)?;
+            let Some(mut grupo) = self.grupos.get(id) else {
+                return Err("Grupo não encontrado".to_string());
+            };
+            grupo.nome = nome;
+            self.grupos.insert(id, &grupo);
+            Ok(())
+        }
+
+        /// Remove um grupo. Os contatos membros não são afetados.
+        #[ink(message)]
+        pub fn remover_grupo(&mut self, id: u32) -> bool {
+            let Some(grupo) = self.grupos.take(id) else {
+                return false;
+            };
+            for contato_id in &grupo.membros {
+                Self::indice_remover(&mut self.grupos_por_contato, contato_id, id);
+            }
+            true
+        }
+
+        #[ink(message)]
+        pub fn ler_grupo(&self, id: u32) -> Option<Grupo> {
+            self.grupos.get(id)
+        }
+
+        #[ink(message)]
+        pub fn listar_grupos(&self) -> Vec<(u32, Grupo)> {
+            (0..self.next_grupo_id)
+                .filter_map(|id| self.grupos.get(id).map(|grupo| (id, grupo)))
+                .collect()
+        }
+
+        /// Adiciona um contato ao grupo. Retorna `false` se ele já era membro.
+        #[ink(message)]
+        pub fn adicionar_ao_grupo(
+            &mut self,
+            grupo_id: u32,
+            contato_id: u32,
+        ) -> Result<bool, String> {
+            let Some(mut grupo) = self.grupos.get(grupo_id) else {
+                return Err("Grupo não encontrado".to_string());
+            };
+            if !self.contatos.contains(contato_id) {
+                return Err("Contato não encontrado".to_string());
+            }
+            let Err(pos) = grupo.membros.binary_search(&contato_id) else {
+                return Ok(false);
+            };
+            if grupo.membros.len() >= MAX_MEMBROS_GRUPO {
+                return Err("Limite de membros do grupo atingido".to_string());
+            }
+
+            grupo.membros.insert(pos, contato_id);
+            self.grupos.insert(grupo_id, &grupo);
+            Self::indice_inserir(&mut self.grupos_por_contato, &contato_id, grupo_id);
+            Ok(true)
+        }
+
+        #[ink(message)]
+        pub fn remover_do_grupo(&mut self, grupo_id: u32, contato_id: u32) -> Result<bool, String> {
+            let Some(mut grupo) = self.grupos.get(grupo_id) else {
+                return Err("Grupo não encontrado".to_string());
+            };
+            let Ok(pos) = grupo.membros.binary_search(&contato_id) else {
+                return Ok(false);
+            };
+
+            grupo.membros.remove(pos);
+            self.grupos.insert(grupo_id, &grupo);
+            Self::indice_remover(&mut self.grupos_por_contato, &contato_id, grupo_id);
+            Ok(true)
+        }
+
+        /// Lista os contatos membros do grupo.
+        #[ink(message)]
+        pub fn membros_do_grupo(&self, grupo_id: u32) -> Result<Vec<(u32, Contato)>, String> {
+            let Some(grupo) = self.grupos.get(grupo_id) else {
+                return Err("Grupo não encontrado".to_string());
+            };
+            Ok(grupo
+                .membros
+                .into_iter()
+                .filter_map(|id| self.contatos.get(id).map(|contato| (id, contato)))
+                .collect())
+        }
+
+        #[ink(message)]
+        pub fn grupos_do_contato(&self, contato_id: u32) -> Vec<(u32, Grupo)> {
+            self.grupos_por_contato
+                .get(contato_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.grupos.get(id).map(|grupo| (id, grupo)))
+                .collect()
+        }
+
+        /// Tira um contato de todos os grupos de que ele faz parte.
+        fn remover_dos_grupos(&mut self, contato_id: u32) {
+            for grupo_id in self.grupos_por_contato.take(contato_id).unwrap_or_default() {
+                if let Some(mut grupo) = self.grupos.get(grupo_id) {
+                    grupo.membros.retain(|id| *id != contato_id);
+                    self.grupos.insert(grupo_id, &grupo);
+                }
+            }
+        }
+
+        // ----- Duplicados -----
+
+        /// Reduz um nome à forma usada na busca por duplicados: minúsculas,
+        /// sem acentos, pontuação ou espaços repetidos.
+        fn normalizar_nome(nome: &str) -> String {
+            let mut normalizado = String::new();
+            for palavra in nome.split_whitespace() {
+                let palavra: String = palavra
+                    .to_lowercase()
+                    .chars()
+                    .map(|c| match c {
+                        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+                        'é' | 'è' | 'ê' | 'ë' => 'e',
+                        'í' | 'ì' | 'î' | 'ï' => 'i',
+                        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+                        'ú' | 'ù' | 'û' | 'ü' => 'u',
+                        'ç' => 'c',
+                        'ñ' => 'n',
+                        c => c,
+                    })
+                    .filter(|c| c.is_alphanumeric())
+                    .collect();
+                if palavra.is_empty() {
+                    continue;
+                }
+                if !normalizado.is_empty() {
+                    normalizado.push(' ');
+                }
+                normalizado.push_str(&palavra);
+            }
+            normalizado
+        }
+
+        /// Lista os pares de contatos que parecem ser a mesma pessoa: os que
+        /// compartilham um telefone ou têm o mesmo nome normalizado e a mesma
+        /// data de aniversário.
+        #[ink(message)]
+        pub fn possiveis_duplicados(&self) -> Vec<PossivelDuplicado> {
+            let mut pares: Vec<PossivelDuplicado> = Vec::new();
+            for id in 0..self.next_contato_id {
+                let Some(contato) = self.contatos.get(id) else {
+                    continue;
+                };
+                let mut candidatos: Vec<(u32, bool, bool)> = Vec::new();
+                for telefone in &contato.telefones {
+                    for outro in self
+                        .contatos_por_telefone
+                        .get(&telefone.numero)
+                        .unwrap_or_default()
+                    {
+                        if outro > id && !candidatos.iter().any(|(c, _, _)| *c == outro) {
+                            candidatos.push((outro, true, false));
+                        }
+                    }
+                }
+                if let Some((dia, mes, _)) = Self::ler_data(&contato.data_aniversario) {
+                    let nome = Self::normalizar_nome(&contato.nome);
+                    for outro in self
+                        .contatos_por_aniversario
+                        .get(Self::chave_aniversario(dia, mes))
+                        .unwrap_or_default()
+                    {
+                        if outro <= id {
+                            continue;
+                        }
+                        let Some(outro_contato) = self.contatos.get(outro) else {
+                            continue;
+                        };
+                        if outro_contato.data_aniversario != contato.data_aniversario
+                            || Self::normalizar_nome(&outro_contato.nome) != nome
+                        {
+                            continue;
+                        }
+                        match candidatos.iter_mut().find(|(c, _, _)| *c == outro) {
+                            Some(candidato) => candidato.2 = true,
+                            None => candidatos.push((outro, false, true)),
+                        }
+                    }
+                }
+                candidatos.sort_by_key(|(c, _, _)| *c);
+                pares.extend(candidatos.into_iter().map(|(outro, telefone, nome)| {
+                    PossivelDuplicado {
+                        contato_a: id,
+                        contato_b: outro,
+                        mesmo_telefone: telefone,
+                        mesmo_nome_e_aniversario: nome,
+                    }
+                }));
+            }
+            pares
+        }
+
+        /// Junta o contato `origem` ao `destino`. O destino mantém seus dados
+        /// e ganha os telefones, e-mails, endereços e tags que só a origem
+        /// tinha; as participações em compromissos e grupos passam para ele.
+        /// Por fim, a origem é movida para a lixeira.
+        #[ink(message)]
+        pub fn mesclar_contatos(&mut self, origem: u32, destino: u32) -> Result<(), String> {
+            if origem == destino {
+                return Err("Origem e destino devem ser contatos diferentes".to_string());
+            }
+            let Some(da_origem) = self.contatos.get(origem) else {
+                return Err("Contato de origem não encontrado".to_string());
+            };
+            let Some(antigo) = self.contatos.get(destino) else {
+                return Err("Contato de destino não encontrado".to_string());
+            };
+
+            let mut novo = antigo.clone();
+            for mut telefone in da_origem.telefones {
+                if !novo.telefones.iter().any(|t| t.numero == telefone.numero) {
+                    telefone.principal = false;
+                    Self::inserir_entrada(&mut novo.telefones, telefone)?;
+                }
+            }
+            for mut email in da_origem.emails {
+                if !novo
+                    .emails
+                    .iter()
+                    .any(|e| e.endereco.eq_ignore_ascii_case(&email.endereco))
+                {
+                    email.principal = false;
+                    Self::inserir_entrada(&mut novo.emails, email)?;
+                }
+            }
+            for mut endereco in da_origem.enderecos {
+                endereco.principal = false;
+                let repetido = novo.enderecos.iter().any(|e| {
+                    Endereco {
+                        principal: false,
+                        ..e.clone()
+                    } == endereco
+                });
+                if !repetido {
+                    Self::inserir_entrada(&mut novo.enderecos, endereco)?;
+                }
+            }
+            for tag in da_origem.tags {
+                Self::inserir_tag(&mut novo.tags, tag)?;
+            }
+
+            let grupos = self.grupos_por_contato.get(origem).unwrap_or_default();
+            if grupos.iter().any(|grupo_id| {
+                self.grupos.get(grupo_id).is_some_and(|g| {
+                    g.membros.binary_search(&destino).is_err()
+                        && g.membros.len() >= MAX_MEMBROS_GRUPO
+                })
+            }) {
+                return Err("Limite de membros do grupo atingido".to_string());
+            }
+
+            for compromisso_id in self
+                .compromissos_por_contato
+                .get(origem)
+                .unwrap_or_default()
+            {
+                if let Some(antigo) = self.compromissos.get(compromisso_id) {
+                    let mut compromisso = antigo.clone();
+                    Self::substituir_participante(&mut compromisso, origem, destino);
+                    self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+                }
+            }
+            // Compromissos na lixeira também passam a citar o destino, para
+            // que a origem não volte como participante ao serem restaurados
+            for compromisso_id in self
+                .lixeira_por_participante
+                .take(origem)
+                .unwrap_or_default()
+            {
+                if let Some(mut item) = self.lixeira_compromissos.get(compromisso_id) {
+                    Self::substituir_participante(&mut item.compromisso, origem, destino);
+                    self.lixeira_compromissos.insert(compromisso_id, &item);
+                    Self::indice_inserir(
+                        &mut self.lixeira_por_participante,
+                        &destino,
+                        compromisso_id,
+                    );
+                }
+            }
+            for grupo_id in grupos {
+                self.remover_do_grupo(grupo_id, origem)?;
+                self.adicionar_ao_grupo(grupo_id, destino)?;
+            }
+
+            // A origem sai dos índices antes de o destino receber seus telefones
+            self.deletar_contato(origem);
+            self.gravar_contato(destino, &antigo, &novo);
+            Ok(())
+        }
+
+        /// Troca `origem` por `destino` entre os participantes, sem repeti-lo.
+        fn substituir_participante(compromisso: &mut Compromisso, origem: u32, destino: u32) {
+            if compromisso.participantes.contains(&destino) {
+                compromisso.participantes.retain(|id| *id != origem);
+            } else {
+                for id in compromisso.participantes.iter_mut() {
+                    if *id == origem {
+                        *id = destino;
+                    }
+                }
+            }
+        }
+
+        /// Lista os contatos que fazem aniversário nos próximos `dias` dias,
+        /// contando a partir da data do bloco atual (hoje incluso), ordenados
+        /// pelos dias restantes.
+        #[ink(message)]
+        pub fn proximos_aniversarios(&self, dias: u32) -> Vec<AniversarioProximo> {
+            let hoje = self.hoje();
+            let (_, _, ano_atual) = Self::data_de_dias(hoje);
+
+            let mut lista = Vec::new();
+            for id in 0..self.next_contato_id {
+                let Some(contato) = self.contatos.get(id) else {
+                    continue;
+                };
+                let Some((dia, mes, ano_nascimento)) = Self::ler_data(&contato.data_aniversario)
+                else {
+                    continue;
+                };
+
+                let mut proximo = Self::aniversario_no_ano(dia, mes, ano_atual);
+                if Self::dias_desde_epoch(proximo.0, proximo.1, proximo.2) < hoje {
+                    proximo = Self::aniversario_no_ano(dia, mes, ano_atual + 1);
+                }
+                let (d, m, a) = proximo;
+                let dias_restantes = (Self::dias_desde_epoch(d, m, a) - hoje) as u32;
+                if dias_restantes > dias {
+                    continue;
+                }
+
+                lista.push(AniversarioProximo {
+                    contato_id: id,
+                    nome: contato.nome,
+                    data: Self::formatar_data(d, m, a),
+                    dias_restantes,
+                    idade_a_completar: a.saturating_sub(ano_nascimento),
+                });
+            }
+            lista.sort_by_key(|a| (a.dias_restantes, a.contato_id));
+            lista
+        }
+
+        // ----- Métodos para Compromissos -----
+
+        /// Cria um novo compromisso na agenda, retornando o seu id e os avisos
+        /// das regras da agenda.
+        #[ink(message)]
+        pub fn criar_compromisso(
+            &mut self,
+            titulo: String,
+            data: String,
+            hora: String,
+            prioridade: Prioridade,
+            duracao: i32,
+            detalhes: DetalhesCompromisso,
+        ) -> Result<(u32, Avisos), String> {
+            if titulo.is_empty() {
+                return Err("Título não pode estar vazio".to_string());
+            }
+
+            if !Self::validar_data(&data) {
+                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            }
+
+            if !Self::validar_hora(&hora) {
+                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            }
+
+            Self::validar_duracao(duracao)?;
+            Self::validar_detalhes(&detalhes)?;
+            let avisos = self.aplicar_regras(&data, &hora, duracao, None)?;
+
+            let id = self.inserir_compromisso(Compromisso {
+                titulo,
+                data,
+                hora,
+                prioridade,
+                duracao,
+                participantes: Vec::new(),
+                lembretes: Vec::new(),
+                status: StatusCompromisso::Agendado,
+                motivo_status: None,
+                tags: Vec::new(),
+                detalhes,
+            });
+            self.emitir_avisos(id, &avisos);
+            Ok((id, avisos))
+        }
+
+        /// Grava um novo compromisso já validado e retorna o seu id.
+        fn inserir_compromisso(&mut self, compromisso: Compromisso) -> u32 {
+            let id = self.next_compromisso_id;
+            self.next_compromisso_id = self.next_compromisso_id.checked_add(1).expect("Overflow");
+            self.compromissos.insert(id, &compromisso);
+            self.indexar_compromisso(id, &compromisso);
+            id
+        }
+
+        /// Lê um compromisso da agenda.
+        #[ink(message)]
+        pub fn ler_compromisso(&self, id: u32) -> Option<Compromisso> {
+            self.compromissos.get(id)
+        }
+
+        /// Atualiza um compromisso da agenda com os dados de `dados`,
+        /// retornando os avisos das regras da agenda.
+        #[ink(message)]
+        pub fn atualizar_compromisso(
+            &mut self,
+            id: u32,
+            dados: NovoCompromisso,
+        ) -> Result<Avisos, String> {
+            let NovoCompromisso {
+                titulo,
+                data,
+                hora,
+                prioridade,
+                duracao,
+                detalhes,
+            } = dados;
+            if titulo.is_empty() {
+                return Err("Título não pode estar vazio".to_string());
+            }
+
+            if !Self::validar_data(&data) {
+                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            }
+
+            if !Self::validar_hora(&hora) {
+                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            }
+
+            Self::validar_duracao(duracao)?;
+            Self::validar_detalhes(&detalhes)?;
+            if let Some(antigo) = self.compromissos.get(id) {
+                // Compromissos cancelados não ocupam a agenda
+                let avisos = if antigo.status == StatusCompromisso::Cancelado {
+                    Avisos::default()
+                } else {
+                    self.aplicar_regras(&data, &hora, duracao, Some(id))?
+                };
+                let mut compromisso = antigo.clone();
+                compromisso.titulo = titulo;
+                compromisso.data = data;
+                compromisso.hora = hora;
+                compromisso.prioridade = prioridade;
+                compromisso.duracao = duracao;
+                compromisso.detalhes = detalhes;
+                self.gravar_compromisso(id, &antigo, &compromisso);
+                self.emitir_avisos(id, &avisos);
+                Ok(avisos)
+            } else {
+                Err("Compromisso não encontrado".to_string())
+            }
+        }
+
+        /// Deleta um compromisso da agenda, movendo-o para a lixeira. Os
+        /// convites e lembretes entregues são mantidos até ele ser apagado.
+        /// Compromissos com reserva ativa não são deletados: devem ser
+        /// cancelados pela reserva, que devolve o pagamento.
+        #[ink(message)]
+        pub fn deletar_compromisso(&mut self, id: u32) -> bool {
+            self.excluir_compromisso(id).is_ok()
+        }
+
+        fn excluir_compromisso(&mut self, id: u32) -> Result<(), String> {
+            let Some(compromisso) = self.compromissos.get(id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            if self.reserva_ativa(id) {
+                return Err("Compromisso reservado deve ser cancelado pela reserva".to_string());
+            }
+            self.purgar_lixeira_expirada();
+            self.desindexar_compromisso(id, &compromisso);
+            self.compromissos.remove(id);
+            for contato_id in &compromisso.participantes {
+                Self::indice_inserir(&mut self.lixeira_por_participante, contato_id, id);
+            }
+            let excluido_em = self.env().block_timestamp();
+            self.lixeira_compromissos.insert(
+                id,
+                &CompromissoNaLixeira {
+                    compromisso,
+                    excluido_em,
+                },
+            );
+            Self::enfileirar(
+                &mut self.compromissos_na_lixeira,
+                &mut self.fila_compromissos_na_lixeira,
+                id,
+                excluido_em,
+            );
+            Ok(())
+        }
+
+        /// Indica se o compromisso foi criado por uma reserva ainda ativa.
+        fn reserva_ativa(&self, compromisso_id: u32) -> bool {
+            self.reserva_por_compromisso
+                .get(compromisso_id)
+                .and_then(|reserva_id| self.reservas.get(reserva_id))
+                .is_some_and(|r| r.situacao == SituacaoReserva::Ativa)
+        }
+
+        /// Atualiza apenas os campos preenchidos em `alteracao`. As regras de
+        /// conflito e de expediente só são verificadas quando a data, a hora
+        /// ou a duração mudam, e os avisos resultantes são retornados.
+        #[ink(message)]
+        pub fn atualizar_compromisso_parcial(
+            &mut self,
+            id: u32,
+            alteracao: AlteracaoCompromisso,
+        ) -> Result<Avisos, String> {
+            if alteracao
+                .titulo
+                .as_ref()
+                .is_some_and(|titulo| titulo.is_empty())
+            {
+                return Err("Título não pode estar vazio".to_string());
+            }
+            if alteracao
+                .data
+                .as_ref()
+                .is_some_and(|data| !Self::validar_data(data))
+            {
+                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            }
+            if alteracao
+                .hora
+                .as_ref()
+                .is_some_and(|hora| !Self::validar_hora(hora))
+            {
+                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            }
+            if let Some(duracao) = alteracao.duracao {
+                Self::validar_duracao(duracao)?;
+            }
+            let Some(antigo) = self.compromissos.get(id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            let mut compromisso = antigo.clone();
+            if let Some(titulo) = alteracao.titulo {
+                compromisso.titulo = titulo;
+            }
+            if let Some(data) = alteracao.data {
+                compromisso.data = data;
+            }
+            if let Some(hora) = alteracao.hora {
+                compromisso.hora = hora;
+            }
+            if let Some(prioridade) = alteracao.prioridade {
+                compromisso.prioridade = prioridade;
+            }
+            if let Some(duracao) = alteracao.duracao {
+                compromisso.duracao = duracao;
+            }
+            if let Some(descricao) = alteracao.descricao {
+                compromisso.detalhes.descricao = descricao;
+            }
+            if let Some(local) = alteracao.local {
+                compromisso.detalhes.local = local;
+            }
+            if let Some(link_reuniao) = alteracao.link_reuniao {
+                compromisso.detalhes.link_reuniao = link_reuniao;
+            }
+            Self::validar_detalhes(&compromisso.detalhes)?;
+
+            let remarcado = compromisso.data != antigo.data
+                || compromisso.hora != antigo.hora
+                || compromisso.duracao != antigo.duracao;
+            let avisos = if remarcado && compromisso.status != StatusCompromisso::Cancelado {
+                self.aplicar_regras(
+                    &compromisso.data,
+                    &compromisso.hora,
+                    compromisso.duracao,
+                    Some(id),
+                )?
+            } else {
+                Avisos::default()
+            };
+            self.gravar_compromisso(id, &antigo, &compromisso);
+            self.emitir_avisos(id, &avisos);
+            Ok(avisos)
+        }
+
+        /// Substitui um compromisso existente, mantendo os índices em dia.
+        fn gravar_compromisso(&mut self, id: u32, antigo: &Compromisso, novo: &Compromisso) {
+            self.desindexar_compromisso(id, antigo);
+            self.compromissos.insert(id, novo);
+            self.indexar_compromisso(id, novo);
+            // Remarcado, o compromisso volta a ter todos os lembretes pendentes;
+            // caso contrário, só valem as confirmações de lembretes mantidos
+            if antigo.data != novo.data || antigo.hora != novo.hora {
+                self.lembretes_confirmados.remove(id);
+            } else if antigo.lembretes != novo.lembretes {
+                let mut confirmados = self.lembretes_confirmados.get(id).unwrap_or_default();
+                confirmados.retain(|a| novo.lembretes.contains(a));
+                if confirmados.is_empty() {
+                    self.lembretes_confirmados.remove(id);
+                } else {
+                    self.lembretes_confirmados.insert(id, &confirmados);
+                }
+            }
+
+            let mut historico = self.historico_compromissos.get(id).unwrap_or_default();
+            let numero = historico.last().map_or(1, |r| r.numero.saturating_add(1));
+            historico.push(RevisaoCompromisso {
+                numero,
+                compromisso: antigo.clone(),
+                editor: self.env().caller(),
+                editado_em: self.env().block_timestamp(),
+            });
+            self.guardar_historico(&mut historico);
+            if historico.is_empty() {
+                self.historico_compromissos.remove(id);
+            } else {
+                self.historico_compromissos.insert(id, &historico);
+            }
+        }
+
+        // ----- Lotes -----
+
+        /// Aplica `operacao` a cada item do lote. No modo `TudoOuNada`, o
+        /// primeiro erro é devolvido como erro da mensagem, o que faz o
+        /// contrato reverter as alterações dos itens anteriores.
+        fn executar_lote<T, R, F>(
+            &mut self,
+            itens: Vec<T>,
+            modo: ModoLote,
+            mut operacao: F,
+        ) -> ResultadoLote<R>
+        where
+            F: FnMut(&mut Self, T) -> Result<R, String>,
+        {
+            if itens.len() > self.max_lote as usize {
+                return Err(ink::prelude::format!(
+                    "Lote muito grande. O máximo é de {} itens.",
+                    self.max_lote
+                ));
+            }
+            let mut resultados = Vec::with_capacity(itens.len());
+            for (posicao, item) in itens.into_iter().enumerate() {
+                let resultado = operacao(self, item);
+                if let (ModoLote::TudoOuNada, Err(erro)) = (modo, &resultado) {
+                    return Err(ink::prelude::format!("Item {posicao}: {erro}"));
+                }
+                resultados.push(resultado);
+            }
+            Ok(resultados)
+        }
+
+        /// Cria vários contatos, devolvendo o resultado de cada um.
+        #[ink(message)]
+        pub fn criar_contatos_em_lote(
+            &mut self,
+            contatos: Vec<NovoContato>,
+            modo: ModoLote,
+        ) -> ResultadoLote<u32> {
+            self.executar_lote(contatos, modo, |agenda, c| {
+                agenda.criar_contato(c.nome, c.telefone, c.idade, c.data_aniversario, c.categoria)
+            })
+        }
+
+        #[ink(message)]
+        pub fn atualizar_contatos_em_lote(
+            &mut self,
+            alteracoes: Vec<(u32, AlteracaoContato)>,
+            modo: ModoLote,
+        ) -> ResultadoLote<()> {
+            self.executar_lote(alteracoes, modo, |agenda, (id, alteracao)| {
+                agenda.atualizar_contato_parcial(id, alteracao)
+            })
+        }
+
+        /// Move vários contatos para a lixeira.
+        #[ink(message)]
+        pub fn deletar_contatos_em_lote(
+            &mut self,
+            ids: Vec<u32>,
+            modo: ModoLote,
+        ) -> ResultadoLote<()> {
+            self.executar_lote(ids, modo, |agenda, id| {
+                if agenda.deletar_contato(id) {
+                    Ok(())
+                } else {
+                    Err("Contato não encontrado".to_string())
+                }
+            })
+        }
+
+        /// Cria vários compromissos, devolvendo o resultado de cada um.
+        #[ink(message)]
+        pub fn criar_compromissos_em_lote(
+            &mut self,
+            compromissos: Vec<NovoCompromisso>,
+            modo: ModoLote,
+        ) -> ResultadoLote<(u32, Avisos)> {
+            self.executar_lote(compromissos, modo, |agenda, c| {
+                agenda.criar_compromisso(
+                    c.titulo,
+                    c.data,
+                    c.hora,
+                    c.prioridade,
+                    c.duracao,
+                    c.detalhes,
+                )
+            })
+        }
+
+        #[ink(message)]
+        pub fn atualizar_compromissos_em_lote(
+            &mut self,
+            alteracoes: Vec<(u32, AlteracaoCompromisso)>,
+            modo: ModoLote,
+        ) -> ResultadoLote<Avisos> {
+            self.executar_lote(alteracoes, modo, |agenda, (id, alteracao)| {
+                agenda.atualizar_compromisso_parcial(id, alteracao)
+            })
+        }
+
+        /// Move vários compromissos para a lixeira.
+        #[ink(message)]
+        pub fn deletar_compromissos_em_lote(
+            &mut self,
+            ids: Vec<u32>,
+            modo: ModoLote,
+        ) -> ResultadoLote<()> {
+            self.executar_lote(ids, modo, |agenda, id| agenda.excluir_compromisso(id))
+        }
+
+        /// Define a maior quantidade de itens aceita numa operação em lote.
+        #[ink(message)]
+        pub fn definir_tamanho_maximo_lote(&mut self, tamanho: u32) -> Result<(), String> {
+            self.garantir_dono()?;
+            if tamanho == 0 || tamanho > MAX_LOTE {
+                return Err("Tamanho de lote inválido".to_string());
+            }
+            self.max_lote = tamanho;
+            Ok(())
+        }
+
+        // ----- Lixeira -----
+
+        fn expirado(&self, excluido_em: Timestamp) -> bool {
+            let retencao = self.retencao_lixeira as u64 * MS_POR_DIA;
+            self.env().block_timestamp() >= excluido_em.saturating_add(retencao)
+        }
+
+        fn enfileirar<KT>(
+            fila: &mut Mapping<u32, (u32, Timestamp), KT>,
+            posicoes: &mut FilaLixeira,
+            id: u32,
+            excluido_em: Timestamp,
+        ) where
+            KT: StorageKey,
+        {
+            fila.insert(posicoes.fim, &(id, excluido_em));
+            posicoes.fim = posicoes.fim.checked_add(1).expect("Overflow");
+        }
+
+        /// Contatos na lixeira, inclusive os expirados, na ordem de exclusão.
+        fn itens_lixeira_contatos(&self) -> Vec<(u32, ContatoNaLixeira)> {
+            let FilaLixeira { inicio, fim } = self.fila_contatos_na_lixeira;
+            (inicio..fim)
+                .filter_map(|pos| self.contatos_na_lixeira.get(pos))
+                .filter_map(|(id, excluido_em)| {
+                    self.lixeira_contatos
+                        .get(id)
+                        .filter(|item| item.excluido_em == excluido_em)
+                        .map(|item| (id, item))
+                })
+                .collect()
+        }
+
+        /// Compromissos na lixeira, inclusive os expirados, na ordem de
+        /// exclusão.
+        fn itens_lixeira_compromissos(&self) -> Vec<(u32, CompromissoNaLixeira)> {
+            let FilaLixeira { inicio, fim } = self.fila_compromissos_na_lixeira;
+            (inicio..fim)
+                .filter_map(|pos| self.compromissos_na_lixeira.get(pos))
+                .filter_map(|(id, excluido_em)| {
+                    self.lixeira_compromissos
+                        .get(id)
+                        .filter(|item| item.excluido_em == excluido_em)
+                        .map(|item| (id, item))
+                })
+                .collect()
+        }
+
+        /// Apaga de vez os registros cujo prazo na lixeira já terminou,
+        /// examinando no máximo `MAX_PURGA` entradas de cada fila. Como as
+        /// filas seguem a ordem de exclusão, basta olhar o começo de cada uma.
+        /// Retorna quantos registros foram apagados.
+        fn purgar_lixeira_expirada(&mut self) -> u32 {
+            self.percorrer_lixeira(true).0
+        }
+
+        /// Retira no máximo `MAX_PURGA` entradas do começo de cada fila da
+        /// lixeira, apagando de vez os registros a que elas se referem. Com
+        /// `somente_expirados`, para na primeira entrada ainda no prazo.
+        /// Retorna quantos registros foram apagados e quantas entradas foram
+        /// retiradas das filas.
+        fn percorrer_lixeira(&mut self, somente_expirados: bool) -> (u32, u32) {
+            let mut apagados = 0;
+            let mut retiradas = 0;
+            for _ in 0..MAX_PURGA {
+                let FilaLixeira { inicio, fim } = self.fila_contatos_na_lixeira;
+                let Some((id, excluido_em)) = self
+                    .contatos_na_lixeira
+                    .get(inicio)
+                    .filter(|_| inicio < fim)
+                else {
+                    break;
+                };
+                let na_lixeira = self
+                    .lixeira_contatos
+                    .get(id)
+                    .is_some_and(|item| item.excluido_em == excluido_em);
+                if na_lixeira {
+                    if somente_expirados && !self.expirado(excluido_em) {
+                        break;
+                    }
+                    self.apagar_contato(id);
+                    apagados += 1;
+                }
+                self.contatos_na_lixeira.remove(inicio);
+                self.fila_contatos_na_lixeira.inicio = inicio + 1;
+                retiradas += 1;
+            }
+            for _ in 0..MAX_PURGA {
+                let FilaLixeira { inicio, fim } = self.fila_compromissos_na_lixeira;
+                let Some((id, excluido_em)) = self
+                    .compromissos_na_lixeira
+                    .get(inicio)
+                    .filter(|_| inicio < fim)
+                else {
+                    break;
+                };
+                let na_lixeira = self
+                    .lixeira_compromissos
+                    .get(id)
+                    .is_some_and(|item| item.excluido_em == excluido_em);
+                if na_lixeira {
+                    if somente_expirados && !self.expirado(excluido_em) {
+                        break;
+                    }
+                    self.apagar_compromisso(id);
+                    apagados += 1;
+                }
+                self.compromissos_na_lixeira.remove(inicio);
+                self.fila_compromissos_na_lixeira.inicio = inicio + 1;
+                retiradas += 1;
+            }
+            (apagados, retiradas)
+        }
+
+        /// Apaga definitivamente os registros cujo prazo na lixeira já
+        /// terminou. Qualquer conta pode chamar; cada chamada examina um
+        /// número limitado de registros. Retorna quantos foram apagados.
+        #[ink(message)]
+        pub fn purgar_lixeira(&mut self) -> u32 {
+            self.purgar_lixeira_expirada()
+        }
+
+        /// Apaga definitivamente um contato da lixeira.
+        fn apagar_contato(&mut self, id: u32) {
+            self.lixeira_contatos.remove(id);
+            self.historico_contatos.remove(id);
+            self.lixeira_por_participante.remove(id);
+            // Compromissos restaurados podem ter mantido o contato como participante
+            self.remover_participacoes(id);
+        }
+
+        /// Apaga definitivamente um compromisso da lixeira.
+        fn apagar_compromisso(&mut self, id: u32) {
+            if let Some(item) = self.lixeira_compromissos.take(id) {
+                self.desindexar_da_lixeira(id, &item.compromisso);
+            }
+            self.remover_convites(id);
+            self.lembretes_confirmados.remove(id);
+            self.historico_compromissos.remove(id);
+        }
+
+        fn desindexar_da_lixeira(&mut self, id: u32, compromisso: &Compromisso) {
+            for contato_id in &compromisso.participantes {
+                Self::indice_remover(&mut self.lixeira_por_participante, contato_id, id);
+            }
+        }
+
+        /// Restaura um contato da lixeira, junto com suas participações em
+        /// compromissos e grupos que ainda existem. Se a categoria do contato
+        /// foi removida nesse meio tempo, ele volta com a categoria padrão.
+        #[ink(message)]
+        pub fn restaurar_contato(&mut self, id: u32) -> Result<(), String> {
+            self.purgar_lixeira_expirada();
+            let Some(item) = self
+                .lixeira_contatos
+                .get(id)
+                .filter(|item| !self.expirado(item.excluido_em))
+            else {
+                return Err("Contato não encontrado na lixeira".to_string());
+            };
+            for telefone in &item.contato.telefones {
+                self.validar_telefone(&telefone.numero, Some(id))?;
+            }
+
+            let mut contato = item.contato;
+            if self.validar_categoria(&contato.categoria).is_err() {
+                contato.categoria = Categoria::default();
+            }
+            self.lixeira_contatos.remove(id);
             self.contatos.insert(id, &contato);
+            self.indexar_contato(id, &contato);
+
+            for compromisso_id in item.compromissos {
+                let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                    continue;
+                };
+                if antigo.participantes.contains(&id) {
+                    Self::indice_inserir(&mut self.compromissos_por_contato, &id, compromisso_id);
+                } else {
+                    let mut compromisso = antigo.clone();
+                    compromisso.participantes.push(id);
+                    self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+                }
+            }
+            for grupo_id in item.grupos {
+                if let Some(mut grupo) = self.grupos.get(grupo_id) {
+                    if let Err(pos) = grupo.membros.binary_search(&id) {
+                        if grupo.membros.len() < MAX_MEMBROS_GRUPO {
+                            grupo.membros.insert(pos, id);
+                            self.grupos.insert(grupo_id, &grupo);
+                            Self::indice_inserir(&mut self.grupos_por_contato, &id, grupo_id);
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /// Restaura um compromisso da lixeira. As regras de conflito e de
+        /// expediente valem como numa remarcação, e participantes apagados
+        /// definitivamente são descartados.
+        #[ink(message)]
+        pub fn restaurar_compromisso(&mut self, id: u32) -> Result<(), String> {
+            self.purgar_lixeira_expirada();
+            let Some(item) = self
+                .lixeira_compromissos
+                .get(id)
+                .filter(|item| !self.expirado(item.excluido_em))
+            else {
+                return Err("Compromisso não encontrado na lixeira".to_string());
+            };
+            let mut compromisso = item.compromisso;
+            let avisos = if compromisso.status == StatusCompromisso::Cancelado {
+                Avisos::default()
+            } else {
+                self.aplicar_regras(
+                    &compromisso.data,
+                    &compromisso.hora,
+                    compromisso.duracao,
+                    Some(id),
+                )?
+            };
+
+            self.desindexar_da_lixeira(id, &compromisso);
+            compromisso
+                .participantes
+                .retain(|c| self.contatos.contains(c) || self.lixeira_contatos.contains(c));
+            self.lixeira_compromissos.remove(id);
+            self.compromissos.insert(id, &compromisso);
+            self.indexar_compromisso(id, &compromisso);
+            self.emitir_avisos(id, &avisos);
+            Ok(())
+        }
+
+        /// Lista os contatos na lixeira, na ordem em que foram excluídos.
+        #[ink(message)]
+        pub fn lixeira_de_contatos(&self) -> Vec<(u32, ContatoNaLixeira)> {
+            self.itens_lixeira_contatos()
+                .into_iter()
+                .filter(|(_, item)| !self.expirado(item.excluido_em))
+                .collect()
+        }
+
+        /// Lista os compromissos na lixeira, na ordem em que foram excluídos.
+        #[ink(message)]
+        pub fn lixeira_de_compromissos(&self) -> Vec<(u32, CompromissoNaLixeira)> {
+            self.itens_lixeira_compromissos()
+                .into_iter()
+                .filter(|(_, item)| !self.expirado(item.excluido_em))
+                .collect()
+        }
+
+        /// Apaga definitivamente o que está na lixeira, examinando no máximo
+        /// `MAX_PURGA` entradas de cada fila por chamada para limitar o gás.
+        /// Retorna quantas entradas foram examinadas; a lixeira só fica vazia
+        /// quando a chamada retorna 0, então repita-a até lá.
+        #[ink(message)]
+        pub fn esvaziar_lixeira(&mut self) -> Result<u32, String> {
+            self.garantir_dono()?;
+            Ok(self.percorrer_lixeira(false).1)
+        }
+
+        /// Define por quantos dias os registros excluídos ficam na lixeira.
+        #[ink(message)]
+        pub fn definir_retencao_lixeira(&mut self, dias: u32) -> Result<(), String> {
+            self.garantir_dono()?;
+            if dias > MAX_RETENCAO {
+                return Err("Retenção muito longa".to_string());
+            }
+            self.retencao_lixeira = dias;
+            Ok(())
+        }
+
+        /// Lista todos os compromissos da agenda, exceto os cancelados.
+        #[ink(message)]
+        pub fn listar_compromissos(&self) -> Vec<Compromisso> {
+            let mut lista = Vec::new();
+            for id in 0..self.next_compromisso_id {
+                if let Some(compromisso) = self.compromissos.get(id) {
+                    if compromisso.status != StatusCompromisso::Cancelado {
+                        lista.push(compromisso);
+                    }
+                }
+            }
+            lista
+        }
+
+        /// Lista os compromissos que estão em um determinado status.
+        #[ink(message)]
+        pub fn listar_compromissos_por_status(
+            &self,
+            status: StatusCompromisso,
+        ) -> Vec<(u32, Compromisso)> {
+            let mut lista = Vec::new();
+            for id in 0..self.next_compromisso_id {
+                if let Some(compromisso) = self.compromissos.get(id) {
+                    if compromisso.status == status {
+                        lista.push((id, compromisso));
+                    }
+                }
+            }
+            lista
+        }
+
+        // ----- Histórico -----
+
+        /// Descarta as revisões mais antigas que passam da profundidade.
+        fn guardar_historico<T>(&self, historico: &mut Vec<T>) {
+            let excesso = historico
+                .len()
+                .saturating_sub(self.profundidade_historico as usize);
+            historico.drain(..excesso);
+        }
+
+        /// Lista as versões anteriores de um contato, da mais antiga para a
+        /// mais recente.
+        #[ink(message)]
+        pub fn historico_contato(&self, id: u32) -> Vec<RevisaoContato> {
+            self.historico_contatos.get(id).unwrap_or_default()
+        }
+
+        #[ink(message)]
+        pub fn historico_compromisso(&self, id: u32) -> Vec<RevisaoCompromisso> {
+            self.historico_compromissos.get(id).unwrap_or_default()
+        }
+
+        /// Volta o contato ao conteúdo da revisão `numero`. A reversão é uma
+        /// alteração como outra qualquer e também fica no histórico.
+        #[ink(message)]
+        pub fn reverter_contato(&mut self, id: u32, numero: u32) -> Result<(), String> {
+            let Some(antigo) = self.contatos.get(id) else {
+                return Err("Contato não encontrado".to_string());
+            };
+            let Some(revisao) = self
+                .historico_contatos
+                .get(id)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|r| r.numero == numero)
+            else {
+                return Err("Revisão não encontrada".to_string());
+            };
+            for telefone in &revisao.contato.telefones {
+                self.validar_telefone(&telefone.numero, Some(id))?;
+            }
+            self.validar_categoria(&revisao.contato.categoria)?;
+            self.gravar_contato(id, &antigo, &revisao.contato);
+            Ok(())
+        }
+
+        /// Volta o compromisso ao conteúdo da revisão `numero`. O status e os
+        /// participantes atuais são mantidos, e as regras de conflito e de
+        /// expediente valem como numa remarcação. Lembretes restaurados que
+        /// não estavam definidos voltam a ficar pendentes.
+        #[ink(message)]
+        pub fn reverter_compromisso(&mut self, id: u32, numero: u32) -> Result<(), String> {
+            let Some(antigo) = self.compromissos.get(id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            let Some(revisao) = self
+                .historico_compromissos
+                .get(id)
+                .unwrap_or_default()
+                .into_iter()
+                .find(|r| r.numero == numero)
+            else {
+                return Err("Revisão não encontrada".to_string());
+            };
+            let compromisso = Compromisso {
+                status: antigo.status,
+                motivo_status: antigo.motivo_status.clone(),
+                participantes: antigo.participantes.clone(),
+                ..revisao.compromisso
+            };
+            let avisos = if compromisso.status == StatusCompromisso::Cancelado {
+                Avisos::default()
+            } else {
+                self.aplicar_regras(
+                    &compromisso.data,
+                    &compromisso.hora,
+                    compromisso.duracao,
+                    Some(id),
+                )?
+            };
+            self.gravar_compromisso(id, &antigo, &compromisso);
+            self.emitir_avisos(id, &avisos);
+            Ok(())
+        }
+
+        /// Define quantas revisões de cada registro são guardadas. Com zero, o
+        /// histórico deixa de ser registrado.
+        #[ink(message)]
+        pub fn definir_profundidade_historico(&mut self, profundidade: u32) -> Result<(), String> {
+            self.garantir_dono()?;
+            if profundidade > MAX_PROFUNDIDADE {
+                return Err("Profundidade do histórico muito grande".to_string());
+            }
+            self.profundidade_historico = profundidade;
+            Ok(())
+        }
+
+        // ----- Status -----
+
+        /// Confirma um compromisso agendado.
+        #[ink(message)]
+        pub fn confirmar_compromisso(
+            &mut self,
+            id: u32,
+            motivo: Option<String>,
+        ) -> Result<(), String> {
+            self.alterar_status(id, StatusCompromisso::Confirmado, motivo)
+        }
+
+        /// Marca um compromisso como em andamento.
+        #[ink(message)]
+        pub fn iniciar_compromisso(
+            &mut self,
+            id: u32,
+            motivo: Option<String>,
+        ) -> Result<(), String> {
+            self.alterar_status(id, StatusCompromisso::EmAndamento, motivo)
+        }
+
+        /// Marca um compromisso como concluído.
+        #[ink(message)]
+        pub fn concluir_compromisso(
+            &mut self,
+            id: u32,
+            motivo: Option<String>,
+        ) -> Result<(), String> {
+            self.alterar_status(id, StatusCompromisso::Concluido, motivo)
+        }
+
+        /// Cancela um compromisso. Compromissos criados por reserva devem ser
+        /// cancelados com `cancelar_reserva`, que trata o reembolso.
+        #[ink(message)]
+        pub fn cancelar_compromisso(
+            &mut self,
+            id: u32,
+            motivo: Option<String>,
+        ) -> Result<(), String> {
+            if self.reserva_ativa(id) {
+                return Err("Compromisso reservado deve ser cancelado pela reserva".to_string());
+            }
+            self.alterar_status(id, StatusCompromisso::Cancelado, motivo)
+        }
+
+        fn alterar_status(
+            &mut self,
+            id: u32,
+            status: StatusCompromisso,
+            motivo: Option<String>,
+        ) -> Result<(), String> {
+            let Some(antigo) = self.compromissos.get(id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            if !antigo.status.pode_mudar_para(status) {
+                return Err(ink::prelude::format!(
+                    "Não é possível passar de {:?} para {:?}",
+                    antigo.status,
+                    status
+                ));
+            }
+            if motivo.as_ref().is_some_and(|m| m.len() > MAX_MOTIVO) {
+                return Err("Motivo muito longo".to_string());
+            }
+
+            let mut compromisso = antigo.clone();
+            compromisso.status = status;
+            compromisso.motivo_status = motivo;
+            self.gravar_compromisso(id, &antigo, &compromisso);
+            self.env().emit_event(StatusAlterado {
+                compromisso_id: id,
+                status,
+            });
+            Ok(())
+        }
+
+        fn atende_filtro(
+            filtro: &FiltroCompromissos,
+            titulo_contem: Option<&str>,
+            compromisso: &Compromisso,
+        ) -> bool {
+            if !filtro.prioridades.is_empty()
+                && !filtro.prioridades.contains(&compromisso.prioridade)
+            {
+                return false;
+            }
+            if let Some(contato_id) = filtro.participante {
+                if !compromisso.participantes.contains(&contato_id) {
+                    return false;
+                }
+            }
+            if !filtro
+                .tags
+                .iter()
+                .all(|tag| compromisso.tags.binary_search(tag).is_ok())
+            {
+                return false;
+            }
+            let status_aceito = if filtro.status.is_empty() {
+                compromisso.status != StatusCompromisso::Cancelado
+            } else {
+                filtro.status.contains(&compromisso.status)
+            };
+            if !status_aceito {
+                return false;
+            }
+            titulo_contem.is_none_or(|t| compromisso.titulo.to_lowercase().contains(t))
+        }
+
+        /// Simula o agendamento de um horário e retorna os compromissos com
+        /// os quais ele se sobrepõe, sem alterar a agenda. `ignorar` permite
+        /// desconsiderar o próprio compromisso ao verificar uma remarcação.
+        #[ink(message)]
+        pub fn verificar_conflitos(
+            &self,
+            data: String,
+            hora: String,
+            duracao: i32,
+            ignorar: Option<u32>,
+        ) -> Result<Vec<u32>, String> {
+            if !Self::validar_data(&data) {
+                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            }
+
+            if !Self::validar_hora(&hora) {
+                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            }
+
+            Ok(self.conflitos(&data, &hora, duracao, ignorar))
+        }
+
+        /// Retorna os intervalos em que a agenda está ocupada entre `inicio` e
+        /// `fim` (timestamps em milissegundos), mesclando compromissos
+        /// sobrepostos. Nenhum detalhe dos compromissos é exposto.
+        #[ink(message)]
+        pub fn livre_ocupado(
+            &self,
+            inicio: Timestamp,
+            fim: Timestamp,
+        ) -> Result<Vec<Intervalo>, String> {
+            if !self.disponibilidade_publica {
+                self.garantir_dono()?;
+            }
+            Self::validar_consulta(inicio, fim)?;
+            Ok(self.intervalos_ocupados(inicio, fim))
+        }
+
+        /// Procura os primeiros horários livres com `duracao` minutos a partir
+        /// de `a_partir_de` (timestamp em milissegundos), olhando no máximo
+        /// `janela` minutos à frente.
+        #[ink(message)]
+        pub fn proximo_horario_livre(
+            &self,
+            duracao: u32,
+            a_partir_de: Timestamp,
+            janela: u32,
+            opcoes: OpcoesBusca,
+        ) -> Result<Vec<Intervalo>, String> {
+            if !self.disponibilidade_publica {
+                self.garantir_dono()?;
+            }
+            if duracao == 0 {
+                return Err("Duração deve ser maior que zero".to_string());
+            }
+            let fim = a_partir_de.saturating_add(janela as u64 * MS_POR_MINUTO);
+            Self::validar_consulta(a_partir_de, fim)?;
+
+            let inicio = a_partir_de.div_ceil(MS_POR_MINUTO) as i64;
+            let fim = (fim / MS_POR_MINUTO) as i64;
+            Ok(self
+                .horarios_livres(duracao as i64, inicio, fim, &opcoes)
+                .into_iter()
+                .map(|(de, ate)| Intervalo {
+                    inicio: de as u64 * MS_POR_MINUTO,
+                    fim: ate as u64 * MS_POR_MINUTO,
+                })
+                .collect())
+        }
+
+        /// Define o expediente de um dia da semana. Enquanto nenhum dia tiver
+        /// expediente, a agenda não restringe horários.
+        #[ink(message)]
+        pub fn definir_expediente(
+            &mut self,
+            dia: DiaSemana,
+            inicio: String,
+            fim: String,
+        ) -> Result<(), String> {
+            self.garantir_dono()?;
+            let (Some(de), Some(ate)) = (Self::ler_hora(&inicio), Self::ler_hora(&fim)) else {
+                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            };
+            if de >= ate {
+                return Err("O fim do expediente deve ser posterior ao início".to_string());
+            }
+
+            let expediente = Expediente { dia, inicio, fim };
+            match self.expediente.binary_search_by_key(&dia, |e| e.dia) {
+                Ok(pos) => self.expediente[pos] = expediente,
+                Err(pos) => self.expediente.insert(pos, expediente),
+            }
+            Ok(())
+        }
+
+        /// Remove o expediente de um dia da semana, que passa a ser folga.
+        #[ink(message)]
+        pub fn remover_expediente(&mut self, dia: DiaSemana) -> Result<bool, String> {
+            self.garantir_dono()?;
+            match self.expediente.binary_search_by_key(&dia, |e| e.dia) {
+                Ok(pos) => {
+                    self.expediente.remove(pos);
+                    Ok(true)
+                }
+                Err(_) => Ok(false),
+            }
+        }
+
+        /// Lista o expediente de cada dia da semana que possui um.
+        #[ink(message)]
+        pub fn listar_expediente(&self) -> Vec<Expediente> {
+            self.expediente.clone()
+        }
+
+        /// Define o que acontece ao criar ou remarcar um compromisso fora do
+        /// expediente.
+        #[ink(message)]
+        pub fn definir_politica_expediente(&mut self, politica: Politica) -> Result<(), String> {
+            self.garantir_dono()?;
+            self.politica_expediente = politica;
+            Ok(())
+        }
+
+        /// Define se qualquer conta pode consultar os horários ocupados da
+        /// agenda.
+        #[ink(message)]
+        pub fn definir_disponibilidade_publica(&mut self, publica: bool) -> Result<(), String> {
+            self.garantir_dono()?;
+            self.disponibilidade_publica = publica;
+            Ok(())
+        }
+
+        /// Define o que acontece ao criar ou remarcar um compromisso que se
+        /// sobrepõe a outro.
+        #[ink(message)]
+        pub fn definir_politica_conflito(&mut self, politica: Politica) -> Result<(), String> {
+            self.garantir_dono()?;
+            self.politica_conflito = politica;
+            Ok(())
+        }
+
+        /// Busca compromissos que atendem a todos os critérios do filtro, em
+        /// ordem cronológica. `pagina` começa em zero.
+        #[ink(message)]
+        pub fn filtrar_compromissos(
+            &self,
+            mut filtro: FiltroCompromissos,
+            pagina: u32,
+            por_pagina: u32,
+        ) -> Result<PaginaCompromissos, String> {
+            if por_pagina == 0 || por_pagina > MAX_POR_PAGINA {
+                return Err("Tamanho de página inválido".to_string());
+            }
+
+            let mut primeiro_dia = i64::MIN;
+            if let Some(data_inicio) = &filtro.data_inicio {
+                let (d, m, a) = Self::ler_data(data_inicio).ok_or_else(|| {
+                    "Data inicial inválida. O formato deve ser dd/mm/aaaa.".to_string()
+                })?;
+                primeiro_dia = Self::dias_desde_epoch(d, m, a);
+            }
+            let mut ultimo_dia = i64::MAX;
+            if let Some(data_fim) = &filtro.data_fim {
+                let (d, m, a) = Self::ler_data(data_fim).ok_or_else(|| {
+                    "Data final inválida. O formato deve ser dd/mm/aaaa.".to_string()
+                })?;
+                ultimo_dia = Self::dias_desde_epoch(d, m, a);
+            }
+            let titulo_contem = filtro.titulo_contem.as_ref().map(|t| t.to_lowercase());
+            filtro.tags = filtro
+                .tags
+                .iter()
+                .map(|tag| Self::normalizar_tag(tag))
+                .collect::<Result<_, _>>()?;
+
+            let pular = pagina.saturating_mul(por_pagina);
+            let mut itens = Vec::new();
+            let mut total = 0u32;
+            for dia in self.dias_com_compromissos(primeiro_dia, ultimo_dia) {
+                let mut do_dia: Vec<(u32, Compromisso)> = self
+                    .compromissos_por_dia
+                    .get(dia)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|id| self.compromissos.get(id).map(|c| (id, c)))
+                    .filter(|(_, c)| Self::atende_filtro(&filtro, titulo_contem.as_deref(), c))
+                    .collect();
+                do_dia.sort_by_key(|(id, c)| (Self::ler_hora(&c.hora), *id));
+
+                for item in do_dia {
+                    if total >= pular && (itens.len() as u32) < por_pagina {
+                        itens.push(item);
+                    }
+                    total += 1;
+                }
+            }
+
+            Ok(PaginaCompromissos { itens, total })
+        }
+
+        // ----- Participantes -----
+
+        /// Adiciona um contato como participante de um compromisso. Retorna
+        /// `false` se ele já participava.
+        #[ink(message)]
+        pub fn adicionar_participante(
+            &mut self,
+            compromisso_id: u32,
+            contato_id: u32,
+        ) -> Result<bool, String> {
+            let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            if !self.contatos.contains(contato_id) {
+                return Err("Contato não encontrado".to_string());
+            }
+            if antigo.participantes.contains(&contato_id) {
+                return Ok(false);
+            }
+
+            let mut compromisso = antigo.clone();
+            compromisso.participantes.push(contato_id);
+            self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+            Ok(true)
+        }
+
+        /// Remove um contato dos participantes de um compromisso. Retorna
+        /// `false` se ele não participava.
+        #[ink(message)]
+        pub fn remover_participante(
+            &mut self,
+            compromisso_id: u32,
+            contato_id: u32,
+        ) -> Result<bool, String> {
+            let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            let Some(pos) = antigo.participantes.iter().position(|id| *id == contato_id) else {
+                return Ok(false);
+            };
+
+            let mut compromisso = antigo.clone();
+            compromisso.participantes.remove(pos);
+            self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+            Ok(true)
+        }
+
+        /// Adiciona todos os membros de um grupo como participantes do
+        /// compromisso. Retorna quantos contatos foram adicionados.
+        #[ink(message)]
+        pub fn adicionar_grupo_ao_compromisso(
+            &mut self,
+            compromisso_id: u32,
+            grupo_id: u32,
+        ) -> Result<u32, String> {
+            let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            let Some(grupo) = self.grupos.get(grupo_id) else {
+                return Err("Grupo não encontrado".to_string());
+            };
+
+            let mut compromisso = antigo.clone();
+            let mut adicionados = 0u32;
+            for contato_id in grupo.membros {
+                if compromisso.participantes.contains(&contato_id) {
+                    continue;
+                }
+                compromisso.participantes.push(contato_id);
+                adicionados += 1;
+            }
+            if adicionados > 0 {
+                self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+            }
+            Ok(adicionados)
+        }
+
+        /// Lista os compromissos de que um contato participa, exceto os
+        /// cancelados.
+        #[ink(message)]
+        pub fn compromissos_do_contato(&self, contato_id: u32) -> Vec<(u32, Compromisso)> {
+            self.compromissos_por_contato
+                .get(contato_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.compromissos.get(id).map(|c| (id, c)))
+                .filter(|(_, c)| c.status != StatusCompromisso::Cancelado)
+                .collect()
+        }
+
+        /// Retira um contato de todos os compromissos de que participa.
+        fn remover_participacoes(&mut self, contato_id: u32) {
+            for compromisso_id in self
+                .compromissos_por_contato
+                .take(contato_id)
+                .unwrap_or_default()
+            {
+                if let Some(antigo) = self.compromissos.get(compromisso_id) {
+                    let mut compromisso = antigo.clone();
+                    compromisso.participantes.retain(|id| *id != contato_id);
+                    self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+                }
+            }
+        }
+
+        // ----- Tags -----
+
+        /// Marca um contato com uma tag. Retorna `false` se ele já a possuía.
+        #[ink(message)]
+        pub fn adicionar_tag_contato(
+            &mut self,
+            contato_id: u32,
+            tag: String,
+        ) -> Result<bool, String> {
+            let tag = Self::normalizar_tag(&tag)?;
+            let mut adicionada = false;
+            self.alterar_contato(contato_id, |contato| {
+                adicionada = Self::inserir_tag(&mut contato.tags, tag)?;
+                Ok(())
+            })?;
+            Ok(adicionada)
+        }
+
+        #[ink(message)]
+        pub fn remover_tag_contato(
+            &mut self,
+            contato_id: u32,
+            tag: String,
+        ) -> Result<bool, String> {
+            let tag = Self::normalizar_tag(&tag)?;
+            let mut removida = false;
+            self.alterar_contato(contato_id, |contato| {
+                removida = Self::retirar_tag(&mut contato.tags, &tag);
+                Ok(())
+            })?;
+            Ok(removida)
+        }
+
+        /// Marca um compromisso com uma tag. Retorna `false` se ele já a possuía.
+        #[ink(message)]
+        pub fn adicionar_tag_compromisso(
+            &mut self,
+            compromisso_id: u32,
+            tag: String,
+        ) -> Result<bool, String> {
+            let tag = Self::normalizar_tag(&tag)?;
+            let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            let mut compromisso = antigo.clone();
+            if !Self::inserir_tag(&mut compromisso.tags, tag)? {
+                return Ok(false);
+            }
+            self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+            Ok(true)
+        }
+
+        #[ink(message)]
+        pub fn remover_tag_compromisso(
+            &mut self,
+            compromisso_id: u32,
+            tag: String,
+        ) -> Result<bool, String> {
+            let tag = Self::normalizar_tag(&tag)?;
+            let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            let mut compromisso = antigo.clone();
+            if !Self::retirar_tag(&mut compromisso.tags, &tag) {
+                return Ok(false);
+            }
+            self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+            Ok(true)
+        }
+
+        #[ink(message)]
+        pub fn contatos_com_tag(&self, tag: String) -> Vec<(u32, Contato)> {
+            let Ok(tag) = Self::normalizar_tag(&tag) else {
+                return Vec::new();
+            };
+            self.contatos_por_tag
+                .get(&tag)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.contatos.get(id).map(|c| (id, c)))
+                .collect()
+        }
+
+        /// Lista os compromissos marcados com a tag, exceto os cancelados.
+        #[ink(message)]
+        pub fn compromissos_com_tag(&self, tag: String) -> Vec<(u32, Compromisso)> {
+            let Ok(tag) = Self::normalizar_tag(&tag) else {
+                return Vec::new();
+            };
+            self.compromissos_por_tag
+                .get(&tag)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.compromissos.get(id).map(|c| (id, c)))
+                .collect()
+        }
+
+        /// Lista as tags em uso, em ordem alfabética, com a quantidade de
+        /// contatos e de compromissos não cancelados marcados com cada uma.
+        #[ink(message)]
+        pub fn tags_em_uso(&self) -> Vec<UsoDeTag> {
+            let mut tags: Vec<String> = (0..self.total_tags_em_uso)
+                .filter_map(|pos| self.tags_em_uso.get(pos))
+                .collect();
+            tags.sort_unstable();
+            tags.into_iter()
+                .map(|tag| UsoDeTag {
+                    contatos: self.contatos_por_tag.get(&tag).unwrap_or_default().len() as u32,
+                    compromissos: self
+                        .compromissos_por_tag
+                        .get(&tag)
+                        .unwrap_or_default()
+                        .len() as u32,
+                    tag,
+                })
+                .collect()
+        }
+
+        // ----- Convites -----
+
+        /// Convida outra conta para um compromisso. Apenas o dono da agenda,
+        /// organizador dos compromissos, pode convidar. Retorna `false` se a
+        /// conta já havia sido convidada.
+        #[ink(message)]
+        pub fn convidar(
+            &mut self,
+            compromisso_id: u32,
+            convidado: AccountId,
+        ) -> Result<bool, String> {
+            self.garantir_dono()?;
+            if !self.compromissos.contains(compromisso_id) {
+                return Err("Compromisso não encontrado".to_string());
+            }
+
+            let mut convites = self.convites.get(compromisso_id).unwrap_or_default();
+            if convites.iter().any(|c| c.convidado == convidado) {
+                return Ok(false);
+            }
+            convites.push(Convite {
+                convidado,
+                resposta: RespostaConvite::Pendente,
+            });
+            self.convites.insert(compromisso_id, &convites);
+            Self::indice_inserir(&mut self.convites_por_conta, &convidado, compromisso_id);
+
+            self.env().emit_event(ConviteEnviado {
+                compromisso_id,
+                convidado,
+            });
+            Ok(true)
+        }
+
+        /// Retira o convite de uma conta para um compromisso.
+        #[ink(message)]
+        pub fn cancelar_convite(
+            &mut self,
+            compromisso_id: u32,
+            convidado: AccountId,
+        ) -> Result<bool, String> {
+            self.garantir_dono()?;
+            let mut convites = self.convites.get(compromisso_id).unwrap_or_default();
+            let Some(pos) = convites.iter().position(|c| c.convidado == convidado) else {
+                return Ok(false);
+            };
+
+            convites.remove(pos);
+            if convites.is_empty() {
+                self.convites.remove(compromisso_id);
+            } else {
+                self.convites.insert(compromisso_id, &convites);
+            }
+            Self::indice_remover(&mut self.convites_por_conta, &convidado, compromisso_id);
+            Ok(true)
+        }
+
+        /// Responde, com a conta que chama, a um convite recebido.
+        #[ink(message)]
+        pub fn responder_convite(
+            &mut self,
+            compromisso_id: u32,
+            resposta: RespostaConvite,
+        ) -> Result<(), String> {
+            if resposta == RespostaConvite::Pendente {
+                return Err("Resposta inválida".to_string());
+            }
+
+            if !self.compromissos.contains(compromisso_id) {
+                return Err("Compromisso não encontrado".to_string());
+            }
+            let convidado = self.env().caller();
+            let mut convites = self.convites.get(compromisso_id).unwrap_or_default();
+            let Some(convite) = convites.iter_mut().find(|c| c.convidado == convidado) else {
+                return Err("Convite não encontrado".to_string());
+            };
+            convite.resposta = resposta;
+            self.convites.insert(compromisso_id, &convites);
+
+            self.env().emit_event(ConviteRespondido {
+                compromisso_id,
+                convidado,
+                resposta,
+            });
+            Ok(())
+        }
+
+        /// Lista os convites recebidos pela conta que chama.
+        #[ink(message)]
+        pub fn meus_convites(&self) -> Vec<ConviteRecebido> {
+            let conta = self.env().caller();
+            self.convites_por_conta
+                .get(conta)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|compromisso_id| {
+                    let compromisso = self.compromissos.get(compromisso_id)?;
+                    let resposta = self
+                        .convites
+                        .get(compromisso_id)?
+                        .into_iter()
+                        .find(|c| c.convidado == conta)?
+                        .resposta;
+                    Some(ConviteRecebido {
+                        compromisso_id,
+                        compromisso,
+                        resposta,
+                    })
+                })
+                .collect()
+        }
+
+        /// Lista os convidados de um compromisso e suas respostas.
+        #[ink(message)]
+        pub fn convites_do_compromisso(&self, compromisso_id: u32) -> Result<Vec<Convite>, String> {
+            self.garantir_dono()?;
+            Ok(self.convites.get(compromisso_id).unwrap_or_default())
+        }
+
+        /// Descarta os convites de um compromisso excluído.
+        fn remover_convites(&mut self, compromisso_id: u32) {
+            for convite in self.convites.take(compromisso_id).unwrap_or_default() {
+                Self::indice_remover(
+                    &mut self.convites_por_conta,
+                    &convite.convidado,
+                    compromisso_id,
+                );
+            }
+        }
+
+        // ----- Reservas -----
+
+        /// Publica uma janela de disponibilidade que qualquer conta pode
+        /// reservar em horários de `duracao_slot` minutos, pagando `preco`
+        /// por horário.
+        #[ink(message)]
+        pub fn publicar_disponibilidade(
+            &mut self,
+            data: String,
+            inicio: String,
+            fim: String,
+            duracao_slot: u32,
+            preco: Balance,
+        ) -> Result<u32, String> {
+            self.garantir_dono()?;
+            let Some((d, m, a)) = Self::ler_data(&data) else {
+                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            };
+            let (Some(de), Some(ate)) = (Self::ler_hora(&inicio), Self::ler_hora(&fim)) else {
+                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            };
+            if duracao_slot == 0 || de + duracao_slot > ate {
+                return Err("A janela deve comportar ao menos um horário".to_string());
+            }
+
+            let dia = Self::dias_desde_epoch(d, m, a) * MINUTOS_POR_DIA;
+            if self.politica_expediente == Politica::Rejeitar
+                && !self.dentro_do_expediente(dia + de as i64, dia + ate as i64)
+            {
+                return Err("Disponibilidade fora do expediente da agenda".to_string());
+            }
+
+            let id = self.next_disponibilidade_id;
+            self.next_disponibilidade_id = self
+                .next_disponibilidade_id
+                .checked_add(1)
+                .expect("Overflow");
+            self.disponibilidades.insert(
+                id,
+                &Disponibilidade {
+                    data,
+                    inicio,
+                    fim,
+                    duracao_slot,
+                    preco,
+                },
+            );
             Ok(id)
         }
 
-        /// Lê um contato da agenda.
-        #[ink(message)]
-        pub fn ler_contato(&self, id: u32) -> Option<Contato> {
-            self.contatos.get(id)
+        /// Retira uma disponibilidade. As reservas já feitas são mantidas.
+        #[ink(message)]
+        pub fn remover_disponibilidade(&mut self, id: u32) -> Result<bool, String> {
+            self.garantir_dono()?;
+            if self.disponibilidades.take(id).is_none() {
+                return Ok(false);
+            }
+            self.horarios_bloqueados.remove(id);
+            Ok(true)
+        }
+
+        #[ink(message)]
+        pub fn ler_disponibilidade(&self, id: u32) -> Option<Disponibilidade> {
+            self.disponibilidades.get(id)
+        }
+
+        /// Lista os horários (hh:mm) ainda livres de uma disponibilidade:
+        /// nem reservados, nem bloqueados, nem ocupados por outro compromisso,
+        /// nem no passado. Como revela quando a agenda está ocupada, segue a
+        /// mesma regra de privacidade de `livre_ocupado`.
+        #[ink(message)]
+        pub fn horarios_disponiveis(&self, disponibilidade_id: u32) -> Result<Vec<String>, String> {
+            if !self.disponibilidade_publica {
+                self.garantir_dono()?;
+            }
+            let Some(disponibilidade) = self.disponibilidades.get(disponibilidade_id) else {
+                return Err("Disponibilidade não encontrada".to_string());
+            };
+            Ok(self
+                .slots(&disponibilidade)
+                .into_iter()
+                .enumerate()
+                .filter(|(slot, _)| {
+                    self.verificar_slot(disponibilidade_id, &disponibilidade, *slot as u32)
+                        .is_ok()
+                })
+                .map(|(_, (hora, _))| hora)
+                .collect())
+        }
+
+        /// Impede que um horário da disponibilidade seja reservado.
+        #[ink(message)]
+        pub fn bloquear_horario(
+            &mut self,
+            disponibilidade_id: u32,
+            horario: String,
+        ) -> Result<(), String> {
+            self.garantir_dono()?;
+            let slot = self.localizar_slot(disponibilidade_id, &horario)?.0;
+            Self::indice_inserir(&mut self.horarios_bloqueados, &disponibilidade_id, slot);
+            Ok(())
+        }
+
+        /// Volta a permitir reservas em um horário bloqueado.
+        #[ink(message)]
+        pub fn liberar_horario(
+            &mut self,
+            disponibilidade_id: u32,
+            horario: String,
+        ) -> Result<(), String> {
+            self.garantir_dono()?;
+            let slot = self.localizar_slot(disponibilidade_id, &horario)?.0;
+            Self::indice_remover(&mut self.horarios_bloqueados, &disponibilidade_id, slot);
+            Ok(())
+        }
+
+        /// Reserva um horário livre de uma disponibilidade, criando o
+        /// compromisso na agenda e registrando a reserva para quem chama.
+        /// Em disponibilidades pagas, o valor transferido deve ser igual ao
+        /// preço e fica retido no contrato até o fim do compromisso.
+        #[ink(message, payable)]
+        pub fn reservar_horario(
+            &mut self,
+            disponibilidade_id: u32,
+            horario: String,
+            titulo: String,
+        ) -> Result<u32, String> {
+            if titulo.is_empty() {
+                return Err("Título não pode estar vazio".to_string());
+            }
+            let (slot, disponibilidade) = self.localizar_slot(disponibilidade_id, &horario)?;
+            self.verificar_slot(disponibilidade_id, &disponibilidade, slot)?;
+
+            let valor = self.env().transferred_value();
+            if valor != disponibilidade.preco {
+                return Err("Valor transferido diferente do preço do horário".to_string());
+            }
+            let inicio = self.slots(&disponibilidade)[slot as usize].1;
+            let fim = inicio + disponibilidade.duracao_slot as i64;
+
+            let reservante = self.env().caller();
+            let compromisso_id = self.inserir_compromisso(Compromisso {
+                titulo,
+                data: disponibilidade.data,
+                hora: horario,
+                prioridade: Prioridade::default(),
+                duracao: disponibilidade.duracao_slot as i32,
+                participantes: Vec::new(),
+                lembretes: Vec::new(),
+                status: StatusCompromisso::Agendado,
+                motivo_status: None,
+                tags: Vec::new(),
+                detalhes: DetalhesCompromisso::default(),
+            });
+
+            let reserva_id = self.next_reserva_id;
+            self.next_reserva_id = self.next_reserva_id.checked_add(1).expect("Overflow");
+            self.reservas.insert(
+                reserva_id,
+                &Reserva {
+                    disponibilidade_id,
+                    slot,
+                    compromisso_id,
+                    reservante,
+                    valor,
+                    inicio: inicio as u64 * MS_POR_MINUTO,
+                    fim: fim as u64 * MS_POR_MINUTO,
+                    situacao: SituacaoReserva::Ativa,
+                },
+            );
+            self.reserva_por_compromisso
+                .insert(compromisso_id, &reserva_id);
+            Self::indice_inserir(
+                &mut self.reservas_por_disponibilidade,
+                &disponibilidade_id,
+                reserva_id,
+            );
+            Self::indice_inserir(&mut self.reservas_por_conta, &reservante, reserva_id);
+
+            self.env().emit_event(HorarioReservado {
+                disponibilidade_id,
+                reserva_id,
+                reservante,
+            });
+            Ok(reserva_id)
+        }
+
+        #[ink(message)]
+        pub fn ler_reserva(&self, id: u32) -> Option<Reserva> {
+            self.reservas.get(id)
+        }
+
+        /// Cancela uma reserva antes do início do compromisso, que passa a
+        /// constar como cancelado. O reservante recebe o reembolso previsto
+        /// na política de cancelamento e o restante vai para o dono; quando é
+        /// o dono quem cancela, o reembolso é integral. Retorna o valor
+        /// reembolsado.
+        #[ink(message)]
+        pub fn cancelar_reserva(&mut self, reserva_id: u32) -> Result<Balance, String> {
+            let Some(mut reserva) = self.reservas.get(reserva_id) else {
+                return Err("Reserva não encontrada".to_string());
+            };
+            let caller = self.env().caller();
+            if caller != reserva.reservante && caller != self.owner {
+                return Err("Apenas o reservante ou o dono podem cancelar a reserva".to_string());
+            }
+            if reserva.situacao != SituacaoReserva::Ativa {
+                return Err("Reserva não está ativa".to_string());
+            }
+            let agora = self.env().block_timestamp();
+            if agora >= reserva.inicio {
+                return Err("Reserva não pode ser cancelada após o início".to_string());
+            }
+
+            let antecedencia = (reserva.inicio - agora) / MS_POR_MINUTO;
+            let politica = &self.politica_cancelamento;
+            let reembolso =
+                if caller == self.owner || antecedencia >= politica.antecedencia_minima as u64 {
+                    reserva.valor
+                } else {
+                    reserva.valor * politica.percentual_tardio as Balance / 100
+                };
+            let retido = reserva.valor - reembolso;
+
+            reserva.situacao = SituacaoReserva::Cancelada;
+            self.reservas.insert(reserva_id, &reserva);
+            if let Some(compromisso) = self.compromissos.get(reserva.compromisso_id) {
+                if compromisso
+                    .status
+                    .pode_mudar_para(StatusCompromisso::Cancelado)
+                {
+                    self.alterar_status(
+                        reserva.compromisso_id,
+                        StatusCompromisso::Cancelado,
+                        Some("Reserva cancelada".to_string()),
+                    )?;
+                }
+            }
+
+            self.transferir(reserva.reservante, reembolso)?;
+            self.transferir(self.owner, retido)?;
+            self.env().emit_event(ReservaCancelada {
+                reserva_id,
+                reembolso,
+            });
+            Ok(reembolso)
+        }
+
+        /// Repassa ao dono o valor retido de uma reserva cujo compromisso já
+        /// terminou. Pode ser chamado por qualquer conta.
+        #[ink(message)]
+        pub fn liberar_pagamento(&mut self, reserva_id: u32) -> Result<Balance, String> {
+            let Some(mut reserva) = self.reservas.get(reserva_id) else {
+                return Err("Reserva não encontrada".to_string());
+            };
+            if reserva.situacao != SituacaoReserva::Ativa {
+                return Err("Reserva não está ativa".to_string());
+            }
+            if self.env().block_timestamp() < reserva.fim {
+                return Err("O compromisso ainda não terminou".to_string());
+            }
+
+            reserva.situacao = SituacaoReserva::Concluida;
+            self.reservas.insert(reserva_id, &reserva);
+
+            self.transferir(self.owner, reserva.valor)?;
+            self.env().emit_event(PagamentoLiberado {
+                reserva_id,
+                valor: reserva.valor,
+            });
+            Ok(reserva.valor)
+        }
+
+        /// Define as regras de reembolso de reservas canceladas.
+        #[ink(message)]
+        pub fn definir_politica_cancelamento(
+            &mut self,
+            politica: PoliticaCancelamento,
+        ) -> Result<(), String> {
+            self.garantir_dono()?;
+            if politica.percentual_tardio > 100 {
+                return Err("Percentual de reembolso deve estar entre 0 e 100".to_string());
+            }
+            self.politica_cancelamento = politica;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn politica_cancelamento(&self) -> PoliticaCancelamento {
+            self.politica_cancelamento.clone()
+        }
+
+        fn transferir(&self, destino: AccountId, valor: Balance) -> Result<(), String> {
+            if valor == 0 {
+                return Ok(());
+            }
+            self.env()
+                .transfer(destino, valor)
+                .map_err(|_| "Falha ao transferir o valor".to_string())
+        }
+
+        /// Lista as reservas feitas pela conta que chama.
+        #[ink(message)]
+        pub fn minhas_reservas(&self) -> Vec<(u32, Reserva)> {
+            self.reservas_por_conta
+                .get(self.env().caller())
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.reservas.get(id).map(|r| (id, r)))
+                .collect()
+        }
+
+        /// Horários de uma disponibilidade como (hh:mm, início em minutos
+        /// desde 01/01/1970).
+        fn slots(&self, disponibilidade: &Disponibilidade) -> Vec<(String, i64)> {
+            let (Some((d, m, a)), Some(mut de), Some(ate)) = (
+                Self::ler_data(&disponibilidade.data),
+                Self::ler_hora(&disponibilidade.inicio),
+                Self::ler_hora(&disponibilidade.fim),
+            ) else {
+                return Vec::new();
+            };
+            let dia = Self::dias_desde_epoch(d, m, a) * MINUTOS_POR_DIA;
+            let mut slots = Vec::new();
+            while disponibilidade.duracao_slot > 0 && de + disponibilidade.duracao_slot <= ate {
+                let hora = ink::prelude::format!("{:02}:{:02}", de / 60, de % 60);
+                slots.push((hora, dia + de as i64));
+                de += disponibilidade.duracao_slot;
+            }
+            slots
+        }
+
+        /// Encontra a posição de um horário (hh:mm) dentro da disponibilidade.
+        fn localizar_slot(
+            &self,
+            disponibilidade_id: u32,
+            horario: &str,
+        ) -> Result<(u32, Disponibilidade), String> {
+            let Some(disponibilidade) = self.disponibilidades.get(disponibilidade_id) else {
+                return Err("Disponibilidade não encontrada".to_string());
+            };
+            let minuto = Self::ler_hora(horario);
+            let slot = self
+                .slots(&disponibilidade)
+                .iter()
+                .position(|(hora, _)| Self::ler_hora(hora) == minuto)
+                .ok_or_else(|| "Horário não pertence à disponibilidade".to_string())?;
+            Ok((slot as u32, disponibilidade))
+        }
+
+        /// Verifica se um horário da disponibilidade pode ser reservado.
+        fn verificar_slot(
+            &self,
+            disponibilidade_id: u32,
+            disponibilidade: &Disponibilidade,
+            slot: u32,
+        ) -> Result<(), String> {
+            let Some((_, inicio)) = self.slots(disponibilidade).into_iter().nth(slot as usize)
+            else {
+                return Err("Horário não pertence à disponibilidade".to_string());
+            };
+
+            if self
+                .horarios_bloqueados
+                .get(disponibilidade_id)
+                .unwrap_or_default()
+                .contains(&slot)
+            {
+                return Err("Horário bloqueado".to_string());
+            }
+
+            let reservado = self
+                .reservas_por_disponibilidade
+                .get(disponibilidade_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.reservas.get(id))
+                .any(|r| r.slot == slot && r.situacao != SituacaoReserva::Cancelada);
+            if reservado {
+                return Err("Horário já reservado".to_string());
+            }
+
+            let agora = (self.env().block_timestamp() / MS_POR_MINUTO) as i64;
+            if inicio < agora {
+                return Err("Horário já passou".to_string());
+            }
+
+            let fim = inicio + disponibilidade.duracao_slot as i64;
+            if !self.ocupacoes(inicio, fim).is_empty() {
+                return Err("Horário ocupado por outro compromisso".to_string());
+            }
+            Ok(())
+        }
+
+        // ----- Lembretes -----
+
+        /// Define com quantos minutos de antecedência o compromisso deve ser
+        /// lembrado. Lembretes já entregues que continuam na lista não são
+        /// entregues de novo.
+        #[ink(message)]
+        pub fn definir_lembretes(
+            &mut self,
+            compromisso_id: u32,
+            mut antecedencias: Vec<u32>,
+        ) -> Result<(), String> {
+            let Some(antigo) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            antecedencias.sort_unstable();
+            antecedencias.dedup();
+            if antecedencias.len() > MAX_LEMBRETES {
+                return Err("Quantidade de lembretes acima do limite".to_string());
+            }
+            if antecedencias.iter().any(|a| *a > MAX_ANTECEDENCIA) {
+                return Err("Antecedência do lembrete acima do limite de 30 dias".to_string());
+            }
+
+            let mut compromisso = antigo.clone();
+            compromisso.lembretes = antecedencias;
+            self.gravar_compromisso(compromisso_id, &antigo, &compromisso);
+            Ok(())
+        }
+
+        /// Lista os lembretes ainda não confirmados que devem ser entregues
+        /// até `agora + janela` (janela em minutos), de compromissos que ainda
+        /// não começaram, em ordem de disparo.
+        #[ink(message)]
+        pub fn lembretes_pendentes(
+            &self,
+            agora: Timestamp,
+            janela: u32,
+        ) -> Result<Vec<LembretePendente>, String> {
+            let limite = agora.saturating_add(janela as u64 * MS_POR_MINUTO);
+            Self::validar_consulta(agora, limite.max(agora.saturating_add(1)))?;
+
+            // Compromissos que começam até `limite` mais a maior antecedência
+            let primeiro_dia = (agora / MS_POR_DIA) as i64;
+            let ultimo_dia = (limite.saturating_add(MAX_ANTECEDENCIA as u64 * MS_POR_MINUTO)
+                / MS_POR_DIA) as i64;
+            let mut pendentes = Vec::new();
+            for dia in self.dias_com_compromissos(primeiro_dia, ultimo_dia) {
+                for id in self.compromissos_por_dia.get(dia).unwrap_or_default() {
+                    let Some(compromisso) = self.compromissos.get(id) else {
+                        continue;
+                    };
+                    if compromisso.lembretes.is_empty()
+                        || matches!(
+                            compromisso.status,
+                            StatusCompromisso::Concluido | StatusCompromisso::Cancelado
+                        )
+                    {
+                        continue;
+                    }
+                    let Some(inicio) = Self::inicio_do_compromisso(&compromisso) else {
+                        continue;
+                    };
+                    let inicio = inicio.max(0) as u64 * MS_POR_MINUTO;
+                    if inicio <= agora {
+                        continue;
+                    }
+
+                    let confirmados = self.lembretes_confirmados.get(id).unwrap_or_default();
+                    for antecedencia in &compromisso.lembretes {
+                        let disparo = inicio.saturating_sub(*antecedencia as u64 * MS_POR_MINUTO);
+                        if disparo <= limite && !confirmados.contains(antecedencia) {
+                            pendentes.push(LembretePendente {
+                                compromisso_id: id,
+                                titulo: compromisso.titulo.clone(),
+                                antecedencia: *antecedencia,
+                                disparo,
+                                inicio,
+                            });
+                        }
+                    }
+                }
+            }
+            pendentes.sort_by_key(|l| (l.disparo, l.compromisso_id));
+            Ok(pendentes)
+        }
+
+        /// Marca um lembrete como entregue, para que não volte a aparecer em
+        /// `lembretes_pendentes`.
+        #[ink(message)]
+        pub fn confirmar_lembrete(
+            &mut self,
+            compromisso_id: u32,
+            antecedencia: u32,
+        ) -> Result<(), String> {
+            let Some(compromisso) = self.compromissos.get(compromisso_id) else {
+                return Err("Compromisso não encontrado".to_string());
+            };
+            if !compromisso.lembretes.contains(&antecedencia) {
+                return Err("Lembrete não encontrado".to_string());
+            }
+            let mut confirmados = self
+                .lembretes_confirmados
+                .get(compromisso_id)
+                .unwrap_or_default();
+            if !confirmados.contains(&antecedencia) {
+                confirmados.push(antecedencia);
+                self.lembretes_confirmados
+                    .insert(compromisso_id, &confirmados);
+            }
+            Ok(())
+        }
+
+        // ----- Visão do Dia -----
+
+        /// Retorna os compromissos não cancelados de uma data, ordenados pela
+        /// hora, e os contatos que fazem aniversário nela. Em anos não
+        /// bissextos, quem nasceu em 29/02 aparece em 28/02.
+        #[ink(message)]
+        pub fn agenda_do_dia(&self, data: String) -> Result<AgendaDoDia, String> {
+            let Some((dia, mes, ano)) = Self::ler_data(&data) else {
+                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            };
+
+            let mut compromissos: Vec<(u32, Compromisso)> = self
+                .compromissos_por_dia
+                .get(Self::dias_desde_epoch(dia, mes, ano))
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|id| self.compromissos.get(id).map(|c| (id, c)))
+                .filter(|(_, c)| c.status != StatusCompromisso::Cancelado)
+                .collect();
+            compromissos.sort_by_key(|(id, c)| (Self::ler_hora(&c.hora), *id));
+
+            let mut ids = self
+                .contatos_por_aniversario
+                .get(Self::chave_aniversario(dia, mes))
+                .unwrap_or_default();
+            if (dia, mes) == (28, 2) && !Self::ano_bissexto(ano) {
+                ids.extend(
+                    self.contatos_por_aniversario
+                        .get(Self::chave_aniversario(29, 2))
+                        .unwrap_or_default(),
+                );
+            }
+            let aniversariantes = ids
+                .into_iter()
+                .filter_map(|id| self.contatos.get(id).map(|c| (id, c)))
+                .collect();
+
+            Ok(AgendaDoDia {
+                data: Self::formatar_data(dia, mes, ano),
+                compromissos,
+                aniversariantes,
+            })
+        }
+
+        // ----- Estatísticas -----
+
+        /// Retorna os números consolidados da agenda. Os totais são mantidos
+        /// pelas operações de escrita; os compromissos passados e futuros, em
+        /// relação ao timestamp do bloco atual, e os minutos por semana
+        /// consideram apenas o período de `data_inicio` a `data_fim`, de no
+        /// máximo 366 dias.
+        #[ink(message)]
+        pub fn estatisticas(
+            &self,
+            data_inicio: String,
+            data_fim: String,
+        ) -> Result<Estatisticas, String> {
+            let Some((d, m, a)) = Self::ler_data(&data_inicio) else {
+                return Err("Data inicial inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            };
+            let primeiro_dia = Self::dias_desde_epoch(d, m, a);
+            let Some((d, m, a)) = Self::ler_data(&data_fim) else {
+                return Err("Data final inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            };
+            let ultimo_dia = Self::dias_desde_epoch(d, m, a);
+            if ultimo_dia < primeiro_dia {
+                return Err("A data final não pode ser anterior à inicial".to_string());
+            }
+            if ultimo_dia - primeiro_dia >= MAX_DIAS_ESTATISTICAS {
+                return Err("Período das estatísticas muito longo".to_string());
+            }
+
+            let contatos_por_categoria = self
+                .categorias()
+                .into_iter()
+                .map(|info| {
+                    let total = self
+                        .contatos_por_categoria
+                        .get(&info.categoria)
+                        .unwrap_or(0);
+                    (info.categoria, total)
+                })
+                .collect();
+            let compromissos_por_prioridade =
+                [Prioridade::Alta, Prioridade::Media, Prioridade::Baixa]
+                    .into_iter()
+                    .map(|prioridade| {
+                        let total = self
+                            .compromissos_por_prioridade
+                            .get(&prioridade)
+                            .unwrap_or(0);
+                        (prioridade, total)
+                    })
+                    .collect();
+            let compromissos_por_status = [
+                StatusCompromisso::Agendado,
+                StatusCompromisso::Confirmado,
+                StatusCompromisso::EmAndamento,
+                StatusCompromisso::Concluido,
+                StatusCompromisso::Cancelado,
+            ]
+            .into_iter()
+            .map(|status| {
+                let total = self.compromissos_por_status.get(status).unwrap_or(0);
+                (status, total)
+            })
+            .collect();
+
+            let agora = (self.env().block_timestamp() / MS_POR_MINUTO) as i64;
+            let hoje = agora.div_euclid(MINUTOS_POR_DIA);
+            let mut compromissos_futuros = 0u32;
+            let mut compromissos_passados = 0u32;
+            for dia in self.dias_com_compromissos(primeiro_dia, ultimo_dia) {
+                if dia < hoje {
+                    compromissos_passados += self.ativos_por_dia.get(dia).unwrap_or(0);
+                } else if dia > hoje {
+                    compromissos_futuros += self.ativos_por_dia.get(dia).unwrap_or(0);
+                } else {
+                    for id in self.compromissos_por_dia.get(dia).unwrap_or_default() {
+                        let Some(compromisso) = self
+                            .compromissos
+                            .get(id)
+                            .filter(|c| c.status != StatusCompromisso::Cancelado)
+                        else {
+                            continue;
+                        };
+                        match Self::inicio_do_compromisso(&compromisso) {
+                            Some(inicio) if inicio < agora => compromissos_passados += 1,
+                            _ => compromissos_futuros += 1,
+                        }
+                    }
+                }
+            }
+
+            let mut minutos_por_semana: Vec<(String, u64)> = Vec::new();
+            let mut semana = Self::inicio_da_semana(primeiro_dia);
+            while semana <= ultimo_dia {
+                let minutos = self.minutos_por_semana.get(semana).unwrap_or(0);
+                if minutos > 0 {
+                    let (d, m, a) = Self::data_de_dias(semana);
+                    minutos_por_semana.push((Self::formatar_data(d, m, a), minutos));
+                }
+                semana += 7;
+            }
+
+            Ok(Estatisticas {
+                total_contatos: self.total_contatos,
+                contatos_por_categoria,
+                total_compromissos: self.total_compromissos,
+                compromissos_por_prioridade,
+                compromissos_por_status,
+                compromissos_futuros,
+                compromissos_passados,
+                minutos_por_semana,
+            })
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn test_criar_contato() {
+            let mut agenda = Agenda::new();
+
+            // Teste criando um contato válido
+            let nome = "John Doe".to_string();
+            let telefone = "123456789".to_string();
+            let idade = 30;
+            let data_aniversario = "01/01/1990".to_string();
+            let categoria = Categoria::Amigo;
+
+            let result = agenda.criar_contato(
+                nome.clone(),
+                telefone.clone(),
+                idade,
+                data_aniversario.clone(),
+                categoria.clone(),
+            );
+            assert!(result.is_ok(), "Falha ao criar contato");
+
+            let id = result.unwrap();
+            let contato = agenda.ler_contato(id).expect("O contato deve existir");
+
+            assert_eq!(contato.nome, nome);
+            assert_eq!(contato.telefone, telefone);
+            assert_eq!(contato.idade, idade);
+            assert_eq!(contato.data_aniversario, data_aniversario);
+            assert_eq!(contato.categoria, categoria);
+        }
+
+        #[ink::test]
+        fn test_criar_contato_data_invalida() {
+            let mut agenda = Agenda::new();
+
+            // Teste criando um contato com data inválida
+            let nome = "John Doe".to_string();
+            let telefone = "123456789".to_string();
+            let idade = 30;
+            let data_aniversario = "32/13/1990".to_string(); // Data inválida
+            let categoria = Categoria::Amigo;
+
+            let result = agenda.criar_contato(nome, telefone, idade, data_aniversario, categoria);
+            assert!(
+                result.is_err(),
+                "Contato não deve ser criado com data inválida"
+            );
+        }
+
+        #[ink::test]
+        fn test_atualizar_contato() {
+            let mut agenda = Agenda::new();
+
+            // Cria um contato válido
+            let nome = "John Doe".to_string();
+            let telefone = "123456789".to_string();
+            let idade = 30;
+            let data_aniversario = "01/01/1990".to_string();
+            let categoria = Categoria::Amigo;
+
+            let id = agenda
+                .criar_contato(
+                    nome.clone(),
+                    telefone.clone(),
+                    idade,
+                    data_aniversario.clone(),
+                    categoria,
+                )
+                .unwrap();
+
+            // Atualiza o contato com novas informações
+            let new_nome = "Jane Doe".to_string();
+            let new_telefone = "987654321".to_string();
+            let new_idade = 31;
+            let new_data_aniversario = "02/02/1990".to_string();
+            let new_categoria = Categoria::Familiar;
+
+            let update_result = agenda.atualizar_contato(
+                id,
+                new_nome.clone(),
+                new_telefone.clone(),
+                new_idade,
+                new_data_aniversario.clone(),
+                new_categoria.clone(),
+            );
+            assert!(update_result.is_ok(), "Falha ao atualizar contato");
+
+            let updated_contato = agenda.ler_contato(id).expect("O contato deve existir");
+            assert_eq!(updated_contato.nome, new_nome);
+            assert_eq!(updated_contato.telefone, new_telefone);
+            assert_eq!(updated_contato.idade, new_idade);
+            assert_eq!(updated_contato.data_aniversario, new_data_aniversario);
+            assert_eq!(updated_contato.categoria, new_categoria);
+        }
+
+        #[ink::test]
+        fn test_atualizar_contato_data_invalida() {
+            let mut agenda = Agenda::new();
+
+            // Cria um contato válido
+            let nome = "John Doe".to_string();
+            let telefone = "123456789".to_string();
+            let idade = 30;
+            let data_aniversario = "01/01/1990".to_string();
+            let categoria = Categoria::Amigo;
+
+            let id = agenda
+                .criar_contato(nome, telefone, idade, data_aniversario, categoria)
+                .unwrap();
+
+            // Tenta atualizar com data inválida
+            let new_data_aniversario = "32/13/1990".to_string(); // Data inválida
+            let update_result = agenda.atualizar_contato(
+                id,
+                "Jane Doe".to_string(),
+                "987654321".to_string(),
+                31,
+                new_data_aniversario,
+                Categoria::Familiar,
+            );
+
+            assert!(
+                update_result.is_err(),
+                "Contato não deve ser atualizado com data inválida"
+            );
+        }
+
+        #[ink::test]
+        fn test_criar_compromisso() {
+            let mut agenda = Agenda::new();
+
+            // Teste criando um compromisso válido
+            let titulo = "Reunião".to_string();
+            let data = "01/01/2025".to_string();
+            let hora = "14:00".to_string();
+            let prioridade = Prioridade::Alta;
+            let duracao = 60;
+
+            let result = agenda.criar_compromisso(
+                titulo.clone(),
+                data.clone(),
+                hora.clone(),
+                prioridade.clone(),
+                duracao,
+                DetalhesCompromisso::default(),
+            );
+            assert!(result.is_ok(), "Falha ao criar compromisso");
+
+            let (id, _) = result.unwrap();
+            let compromisso = agenda
+                .ler_compromisso(id)
+                .expect("O compromisso deve existir");
+
+            assert_eq!(compromisso.titulo, titulo);
+            assert_eq!(compromisso.data, data);
+            assert_eq!(compromisso.hora, hora);
+            assert_eq!(compromisso.prioridade, prioridade);
+            assert_eq!(compromisso.duracao, duracao);
+        }
+
+        #[ink::test]
+        fn test_criar_compromisso_data_invalida() {
+            let mut agenda = Agenda::new();
+
+            // Teste criando um compromisso com data inválida
+            let titulo = "Reunião".to_string();
+            let data = "32/13/2025".to_string(); // Data inválida
+            let hora = "14:00".to_string();
+            let prioridade = Prioridade::Alta;
+            let duracao = 60;
+
+            let result = agenda.criar_compromisso(
+                titulo,
+                data,
+                hora,
+                prioridade,
+                duracao,
+                DetalhesCompromisso::default(),
+            );
+            assert!(
+                result.is_err(),
+                "Compromisso não deve ser criado com data inválida"
+            );
+        }
+
+        #[ink::test]
+        fn test_atualizar_compromisso() {
+            let mut agenda = Agenda::new();
+
+            // Cria um compromisso válido
+            let titulo = "Reunião".to_string();
+            let data = "01/01/2025".to_string();
+            let hora = "14:00".to_string();
+            let prioridade = Prioridade::Alta;
+            let duracao = 60;
+
+            let id = agenda
+                .criar_compromisso(
+                    titulo.clone(),
+                    data.clone(),
+                    hora.clone(),
+                    prioridade,
+                    duracao,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+
+            // Atualiza o compromisso com novas informações
+            let new_titulo = "Conferência".to_string();
+            let new_data = "02/01/2025".to_string();
+            let new_hora = "10:00".to_string();
+            let new_prioridade = Prioridade::Media;
+            let new_duracao = 90;
+            let new_detalhes = DetalhesCompromisso {
+                descricao: Some("Planejamento anual".to_string()),
+                local: None,
+                link_reuniao: Some("https://meet.exemplo.com/anual".to_string()),
+            };
+
+            let update_result = agenda.atualizar_compromisso(
+                id,
+                NovoCompromisso {
+                    titulo: new_titulo.clone(),
+                    data: new_data.clone(),
+                    hora: new_hora.clone(),
+                    prioridade: new_prioridade.clone(),
+                    duracao: new_duracao,
+                    detalhes: new_detalhes.clone(),
+                },
+            );
+            assert!(update_result.is_ok(), "Falha ao atualizar compromisso");
+
+            let updated_compromisso = agenda
+                .ler_compromisso(id)
+                .expect("O compromisso deve existir");
+            assert_eq!(updated_compromisso.titulo, new_titulo);
+            assert_eq!(updated_compromisso.data, new_data);
+            assert_eq!(updated_compromisso.hora, new_hora);
+            assert_eq!(updated_compromisso.prioridade, new_prioridade);
+            assert_eq!(updated_compromisso.duracao, new_duracao);
+            assert_eq!(updated_compromisso.detalhes, new_detalhes);
+        }
+
+        #[ink::test]
+        fn test_deletar_contato() {
+            let mut agenda = Agenda::new();
+
+            // Cria um contato válido
+            let nome = "John Doe".to_string();
+            let telefone = "123456789".to_string();
+            let idade = 30;
+            let data_aniversario = "01/01/1990".to_string();
+            let categoria = Categoria::Amigo;
+
+            let id = agenda
+                .criar_contato(nome, telefone, idade, data_aniversario, categoria)
+                .unwrap();
+
+            // Deleta o contato
+            let delete_result = agenda.deletar_contato(id);
+            assert!(delete_result, "O contato deve ser deletado");
+
+            // Garante que o contato não existe mais
+            let deleted_contato = agenda.ler_contato(id);
+            assert!(
+                deleted_contato.is_none(),
+                "O contato não deve existir após a exclusão"
+            );
+        }
+
+        #[ink::test]
+        fn test_deletar_compromisso() {
+            let mut agenda = Agenda::new();
+
+            // Cria um compromisso válido
+            let titulo = "Reunião".to_string();
+            let data = "01/01/2025".to_string();
+            let hora = "14:00".to_string();
+            let prioridade = Prioridade::Alta;
+            let duracao = 60;
+
+            let id = agenda
+                .criar_compromisso(
+                    titulo,
+                    data,
+                    hora,
+                    prioridade,
+                    duracao,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+
+            // Deleta o compromisso
+            let delete_result = agenda.deletar_compromisso(id);
+            assert!(delete_result, "O compromisso deve ser deletado");
+
+            // Garante que o compromisso não existe mais
+            let deleted_compromisso = agenda.ler_compromisso(id);
+            assert!(
+                deleted_compromisso.is_none(),
+                "O compromisso não deve existir após a exclusão"
+            );
+        }
+
+        #[ink::test]
+        fn test_meses_com_30_dias_invalidos() {
+            let mut agenda = Agenda::new();
+            let datas_invalidas_30 = vec!["04/31/1990", "06/31/1995", "09/31/2000", "11/31/2020"];
+            for data in datas_invalidas_30 {
+                let result = agenda.criar_contato(
+                    "Teste".to_string(),
+                    "123456789".to_string(),
+                    30,
+                    data.to_string(),
+                    Categoria::Colega,
+                );
+                assert!(
+                    result.is_err(),
+                    "Contato criado com data inválida: {}",
+                    data
+                );
+            }
+        }
+
+        #[ink::test]
+        fn test_datas_gerais_invalidas() {
+            let mut agenda = Agenda::new();
+            let data_invalida = "13/32/2023"; // Mês e dia inválidos
+            let result = agenda.criar_contato(
+                "Teste".to_string(),
+                "123456789".to_string(),
+                30,
+                data_invalida.to_string(),
+                Categoria::Amigo,
+            );
+            assert!(
+                result.is_err(),
+                "Contato criado com data inválida: {}",
+                data_invalida
+            );
+        }
+
+        fn definir_data_do_bloco(dia: u32, mes: u32, ano: u32) {
+            let dias = Agenda::dias_desde_epoch(dia, mes, ano) as u64;
+            ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(dias * MS_POR_DIA);
+        }
+
+        #[ink::test]
+        fn test_proximos_aniversarios() {
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(30, 12, 2024);
+
+            let ano_novo = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "111111111".to_string(),
+                    34,
+                    "01/01/1990".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            let vespera = agenda
+                .criar_contato(
+                    "Bruno".to_string(),
+                    "222222222".to_string(),
+                    23,
+                    "31/12/2000".to_string(),
+                    Categoria::Familiar,
+                )
+                .unwrap();
+            agenda
+                .criar_contato(
+                    "Carla".to_string(),
+                    "333333333".to_string(),
+                    44,
+                    "15/06/1980".to_string(),
+                    Categoria::Colega,
+                )
+                .unwrap();
+
+            let proximos = agenda.proximos_aniversarios(10);
+            assert_eq!(proximos.len(), 2);
+
+            // Ordenados pelos dias restantes, atravessando a virada do ano
+            assert_eq!(proximos[0].contato_id, vespera);
+            assert_eq!(proximos[0].dias_restantes, 1);
+            assert_eq!(proximos[0].idade_a_completar, 24);
+            assert_eq!(proximos[1].contato_id, ano_novo);
+            assert_eq!(proximos[1].data, "01/01/2025");
+            assert_eq!(proximos[1].dias_restantes, 2);
+            assert_eq!(proximos[1].idade_a_completar, 35);
+        }
+
+        #[ink::test]
+        fn test_proximos_aniversarios_29_de_fevereiro() {
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(27, 2, 2025);
+
+            let id = agenda
+                .criar_contato(
+                    "Diego".to_string(),
+                    "444444444".to_string(),
+                    24,
+                    "29/02/2000".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+
+            // Em ano não bissexto o aniversário é comemorado em 28/02
+            let proximos = agenda.proximos_aniversarios(1);
+            assert_eq!(proximos.len(), 1);
+            assert_eq!(proximos[0].contato_id, id);
+            assert_eq!(proximos[0].data, "28/02/2025");
+            assert_eq!(proximos[0].idade_a_completar, 25);
+
+            // No dia do aniversário ele ainda aparece, com zero dias restantes
+            definir_data_do_bloco(28, 2, 2025);
+            assert_eq!(agenda.proximos_aniversarios(0)[0].dias_restantes, 0);
+        }
+
+        #[ink::test]
+        fn test_contato_por_telefone() {
+            let mut agenda = Agenda::new();
+
+            let id = agenda
+                .criar_contato(
+                    "John Doe".to_string(),
+                    "+55 (11) 98765-4321".to_string(),
+                    30,
+                    "01/01/1990".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+
+            // O telefone é armazenado normalizado
+            let contato = agenda.ler_contato(id).unwrap();
+            assert_eq!(contato.telefone, "+5511987654321");
+
+            // A busca ignora a formatação
+            let encontrados = agenda.contato_por_telefone("+55 11 98765 4321".to_string());
+            assert_eq!(encontrados, vec![(id, contato)]);
+
+            // Após atualizar o telefone, o índice acompanha a mudança
+            agenda
+                .atualizar_contato(
+                    id,
+                    "John Doe".to_string(),
+                    "11 1234-5678".to_string(),
+                    30,
+                    "01/01/1990".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            assert!(agenda
+                .contato_por_telefone("+5511987654321".to_string())
+                .is_empty());
+            assert_eq!(
+                agenda.contato_por_telefone("1112345678".to_string()).len(),
+                1
+            );
+
+            agenda.deletar_contato(id);
+            assert!(agenda
+                .contato_por_telefone("1112345678".to_string())
+                .is_empty());
+        }
+
+        #[ink::test]
+        fn test_telefone_unico() {
+            let mut agenda = Agenda::new();
+
+            let criar = |agenda: &mut Agenda, telefone: &str| {
+                agenda.criar_contato(
+                    "John Doe".to_string(),
+                    telefone.to_string(),
+                    30,
+                    "01/01/1990".to_string(),
+                    Categoria::Amigo,
+                )
+            };
+
+            // Por padrão telefones repetidos são permitidos
+            criar(&mut agenda, "123456789").unwrap();
+            assert!(criar(&mut agenda, "123-456-789").is_ok());
+            assert_eq!(
+                agenda.contato_por_telefone("123456789".to_string()).len(),
+                2
+            );
+
+            assert!(criar(&mut agenda, "sem número").is_err());
+
+            agenda.definir_telefone_unico(true).unwrap();
+            assert!(criar(&mut agenda, "(12) 3456-789").is_err());
+            assert!(criar(&mut agenda, "987654321").is_ok());
+
+            // O próprio contato pode manter o seu telefone ao ser atualizado
+            agenda.definir_telefone_unico(false).unwrap();
+            let outro = criar(&mut agenda, "555555555").unwrap();
+            agenda.definir_telefone_unico(true).unwrap();
+            assert!(agenda
+                .atualizar_contato(
+                    outro,
+                    "Jane Doe".to_string(),
+                    "555555555".to_string(),
+                    31,
+                    "02/02/1990".to_string(),
+                    Categoria::Familiar,
+                )
+                .is_ok());
+            assert!(agenda
+                .atualizar_contato(
+                    outro,
+                    "Jane Doe".to_string(),
+                    "987654321".to_string(),
+                    31,
+                    "02/02/1990".to_string(),
+                    Categoria::Familiar,
+                )
+                .is_err());
+
+            // Apenas o dono pode alterar a configuração
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.definir_telefone_unico(false).is_err());
         }
 
-        /// Atualiza um contato da agenda.
-        #[ink(message)]
-        pub fn atualizar_contato(
-            &mut self,
-            id: u32,
-            nome: String,
-            telefone: String,
-            idade: u32,
-            data_aniversario: String,
-            categoria: Categoria,
-        ) -> Result<bool, String> {
-            if nome.is_empty() {
-                return Err("Nome não pode estar vazio".to_string());
-            }
+        #[ink::test]
+        fn test_estatisticas() {
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(15, 1, 2025); // quarta-feira
+            let janeiro = |agenda: &Agenda| {
+                agenda
+                    .estatisticas("01/01/2025".to_string(), "31/01/2025".to_string())
+                    .unwrap()
+            };
 
-            if telefone.is_empty() {
-                return Err("Telefone não pode estar vazio".to_string());
+            for (telefone, categoria) in [
+                ("111111111", Categoria::Amigo),
+                ("222222222", Categoria::Amigo),
+                ("333333333", Categoria::Familiar),
+            ] {
+                agenda
+                    .criar_contato(
+                        "Teste".to_string(),
+                        telefone.to_string(),
+                        30,
+                        "01/01/1990".to_string(),
+                        categoria,
+                    )
+                    .unwrap();
             }
 
-            if !Self::validar_data(&data_aniversario) {
-                return Err(
-                    "Data de aniversário inválida. O formato deve ser dd/mm/aaaa.".to_string(),
-                );
-            }
+            let passado = agenda
+                .criar_compromisso(
+                    "Retrospectiva".to_string(),
+                    "13/01/2025".to_string(),
+                    "10:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            agenda
+                .criar_compromisso(
+                    "Planejamento".to_string(),
+                    "16/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Media,
+                    30,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap();
+            agenda
+                .criar_compromisso(
+                    "Revisão".to_string(),
+                    "20/01/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Media,
+                    45,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap();
 
-            if let Some(mut contato) = self.contatos.get(id) {
-                contato.nome = nome;
-                contato.telefone = telefone;
-                contato.idade = idade;
-                contato.data_aniversario = data_aniversario;
-                contato.categoria = categoria;
-                self.contatos.insert(id, &contato);
-                Ok(true)
-            } else {
-                Err("Contato não encontrado".to_string())
-            }
+            let estatisticas = janeiro(&agenda);
+            assert_eq!(estatisticas.total_contatos, 3);
+            assert_eq!(
+                estatisticas.contatos_por_categoria,
+                vec![
+                    (Categoria::Amigo, 2),
+                    (Categoria::Familiar, 1),
+                    (Categoria::Colega, 0)
+                ]
+            );
+            assert_eq!(estatisticas.total_compromissos, 3);
+            assert_eq!(
+                estatisticas.compromissos_por_prioridade,
+                vec![
+                    (Prioridade::Alta, 1),
+                    (Prioridade::Media, 2),
+                    (Prioridade::Baixa, 0)
+                ]
+            );
+            assert_eq!(estatisticas.compromissos_passados, 1);
+            assert_eq!(estatisticas.compromissos_futuros, 2);
+            assert_eq!(
+                estatisticas.minutos_por_semana,
+                vec![
+                    ("13/01/2025".to_string(), 90),
+                    ("20/01/2025".to_string(), 45)
+                ]
+            );
+
+            // Passados, futuros e minutos consideram apenas o período consultado
+            let semana = agenda
+                .estatisticas("20/01/2025".to_string(), "26/01/2025".to_string())
+                .unwrap();
+            assert_eq!(semana.total_compromissos, 3);
+            assert_eq!(semana.compromissos_passados, 0);
+            assert_eq!(semana.compromissos_futuros, 1);
+            assert_eq!(
+                semana.minutos_por_semana,
+                vec![("20/01/2025".to_string(), 45)]
+            );
+            assert!(agenda
+                .estatisticas("26/01/2025".to_string(), "20/01/2025".to_string())
+                .is_err());
+            assert!(agenda
+                .estatisticas("01/01/2025".to_string(), "01/01/2026".to_string())
+                .is_ok());
+            assert!(agenda
+                .estatisticas("01/01/2025".to_string(), "02/01/2026".to_string())
+                .is_err());
+
+            // Atualizações e exclusões mantêm os contadores em dia
+            agenda
+                .atualizar_compromisso(
+                    passado,
+                    NovoCompromisso {
+                        titulo: "Retrospectiva".to_string(),
+                        data: "15/01/2025".to_string(),
+                        hora: "18:00".to_string(),
+                        prioridade: Prioridade::Baixa,
+                        duracao: 120,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .unwrap();
+            agenda.deletar_contato(0);
+
+            let estatisticas = janeiro(&agenda);
+            assert_eq!(estatisticas.total_contatos, 2);
+            assert_eq!(
+                estatisticas.contatos_por_categoria[0],
+                (Categoria::Amigo, 1)
+            );
+            assert_eq!(
+                estatisticas.compromissos_por_prioridade,
+                vec![
+                    (Prioridade::Alta, 0),
+                    (Prioridade::Media, 2),
+                    (Prioridade::Baixa, 1)
+                ]
+            );
+            assert_eq!(estatisticas.compromissos_passados, 0);
+            assert_eq!(estatisticas.compromissos_futuros, 3);
+            assert_eq!(
+                estatisticas.minutos_por_semana,
+                vec![
+                    ("13/01/2025".to_string(), 150),
+                    ("20/01/2025".to_string(), 45)
+                ]
+            );
+
+            // Compromissos cancelados deixam de contar nos totais e nas tags
+            agenda
+                .adicionar_tag_compromisso(1, "projeto".to_string())
+                .unwrap();
+            agenda
+                .adicionar_tag_compromisso(2, "projeto".to_string())
+                .unwrap();
+            agenda.cancelar_compromisso(2, None).unwrap();
+            let estatisticas = janeiro(&agenda);
+            assert_eq!(estatisticas.total_compromissos, 3);
+            assert_eq!(estatisticas.compromissos_passados, 0);
+            assert_eq!(estatisticas.compromissos_futuros, 2);
+            assert_eq!(
+                estatisticas.minutos_por_semana,
+                vec![("13/01/2025".to_string(), 150)]
+            );
+            assert_eq!(
+                agenda.tags_em_uso(),
+                vec![UsoDeTag {
+                    tag: "projeto".to_string(),
+                    contatos: 0,
+                    compromissos: 1
+                }]
+            );
+            assert_eq!(agenda.compromissos_com_tag("projeto".to_string()).len(), 1);
+
+            // Excluir o cancelado não altera os totais dos ativos
+            assert!(agenda.deletar_compromisso(2));
+            let estatisticas = janeiro(&agenda);
+            assert_eq!(estatisticas.total_compromissos, 2);
+            assert_eq!(estatisticas.compromissos_futuros, 2);
+            assert_eq!(agenda.tags_em_uso().len(), 1);
         }
 
-        /// Deleta um contato da agenda.
-        #[ink(message)]
-        pub fn deletar_contato(&mut self, id: u32) -> bool {
-            if self.contatos.contains(id) {
-                self.contatos.remove(id);
-                true
-            } else {
-                false
-            }
+        #[ink::test]
+        fn test_agenda_do_dia() {
+            let mut agenda = Agenda::new();
+
+            let tarde = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "28/02/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            let manha = agenda
+                .criar_compromisso(
+                    "Café".to_string(),
+                    "28/02/2025".to_string(),
+                    "08:30".to_string(),
+                    Prioridade::Baixa,
+                    30,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            agenda
+                .criar_compromisso(
+                    "Outro dia".to_string(),
+                    "01/03/2025".to_string(),
+                    "08:00".to_string(),
+                    Prioridade::Baixa,
+                    30,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap();
+
+            let aniversariante = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "111111111".to_string(),
+                    30,
+                    "28/02/1995".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            let bissexto = agenda
+                .criar_contato(
+                    "Bruno".to_string(),
+                    "222222222".to_string(),
+                    24,
+                    "29/02/2000".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+
+            let dia = agenda.agenda_do_dia("28/02/2025".to_string()).unwrap();
+            let compromissos: Vec<u32> = dia.compromissos.iter().map(|(id, _)| *id).collect();
+            assert_eq!(compromissos, vec![manha, tarde]);
+            let aniversariantes: Vec<u32> = dia.aniversariantes.iter().map(|(id, _)| *id).collect();
+            assert_eq!(aniversariantes, vec![aniversariante, bissexto]);
+
+            // Em ano bissexto, cada um no seu dia
+            let dia = agenda.agenda_do_dia("28/02/2024".to_string()).unwrap();
+            assert!(dia.compromissos.is_empty());
+            assert_eq!(dia.aniversariantes.len(), 1);
+            let dia = agenda.agenda_do_dia("29/02/2024".to_string()).unwrap();
+            assert_eq!(dia.aniversariantes[0].0, bissexto);
+
+            assert!(agenda.agenda_do_dia("30/02/2025".to_string()).is_err());
         }
 
-        /// Lista todos os contatos da agenda.
-        #[ink(message)]
-        pub fn listar_contatos(&self) -> Vec<Contato> {
-            let mut lista = Vec::new();
-            for id in 0..self.next_contato_id {
-                if let Some(contato) = self.contatos.get(id) {
-                    lista.push(contato);
-                }
+        #[ink::test]
+        fn test_filtrar_compromissos() {
+            let mut agenda = Agenda::new();
+
+            let dados = [
+                ("Reunião de vendas", "10/01/2025", "14:00", Prioridade::Alta),
+                ("Almoço", "10/01/2025", "12:00", Prioridade::Baixa),
+                ("Reunião geral", "12/01/2025", "09:00", Prioridade::Media),
+                ("Dentista", "15/01/2025", "08:00", Prioridade::Alta),
+                ("Reunião anual", "20/02/2025", "10:00", Prioridade::Alta),
+            ];
+            for (titulo, data, hora, prioridade) in dados {
+                agenda
+                    .criar_compromisso(
+                        titulo.to_string(),
+                        data.to_string(),
+                        hora.to_string(),
+                        prioridade,
+                        60,
+                        DetalhesCompromisso::default(),
+                    )
+                    .unwrap();
             }
-            lista
+
+            // Sem critérios, todos os compromissos em ordem cronológica
+            let pagina = agenda
+                .filtrar_compromissos(FiltroCompromissos::default(), 0, 10)
+                .unwrap();
+            assert_eq!(pagina.total, 5);
+            let ids: Vec<u32> = pagina.itens.iter().map(|(id, _)| *id).collect();
+            assert_eq!(ids, vec![1, 0, 2, 3, 4]);
+
+            // Critérios combinados
+            let filtro = FiltroCompromissos {
+                data_inicio: Some("10/01/2025".to_string()),
+                data_fim: Some("31/01/2025".to_string()),
+                prioridades: vec![Prioridade::Alta, Prioridade::Media],
+                titulo_contem: Some("REUNIÃO".to_string()),
+                ..Default::default()
+            };
+            let pagina = agenda.filtrar_compromissos(filtro.clone(), 0, 10).unwrap();
+            assert_eq!(pagina.total, 2);
+            let titulos: Vec<String> = pagina.itens.into_iter().map(|(_, c)| c.titulo).collect();
+            assert_eq!(titulos, vec!["Reunião de vendas", "Reunião geral"]);
+
+            // Paginação
+            let pagina = agenda.filtrar_compromissos(filtro, 1, 1).unwrap();
+            assert_eq!(pagina.total, 2);
+            assert_eq!(pagina.itens.len(), 1);
+            assert_eq!(pagina.itens[0].1.titulo, "Reunião geral");
+
+            assert!(agenda
+                .filtrar_compromissos(FiltroCompromissos::default(), 0, 0)
+                .is_err());
+            let filtro_invalido = FiltroCompromissos {
+                data_inicio: Some("31/02/2025".to_string()),
+                ..Default::default()
+            };
+            assert!(agenda.filtrar_compromissos(filtro_invalido, 0, 10).is_err());
         }
 
-        // ----- Métodos para Compromissos -----
+        #[ink::test]
+        fn test_conflitos_de_horario() {
+            let mut agenda = Agenda::new();
 
-        /// Cria um novo compromisso na agenda.
-        #[ink(message)]
-        pub fn criar_compromisso(
-            &mut self,
-            titulo: String,
-            data: String,
-            hora: String,
-            prioridade: Prioridade,
-            duracao: i32,
-        ) -> Result<u32, String> {
-            if titulo.is_empty() {
-                return Err("Título não pode estar vazio".to_string());
-            }
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            let plantao = agenda
+                .criar_compromisso(
+                    "Plantão".to_string(),
+                    "10/01/2025".to_string(),
+                    "23:30".to_string(),
+                    Prioridade::Media,
+                    120,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
 
-            if !Self::validar_data(&data) {
-                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
+            let verificar = |agenda: &Agenda, data: &str, hora: &str, duracao: i32| {
+                agenda
+                    .verificar_conflitos(data.to_string(), hora.to_string(), duracao, None)
+                    .unwrap()
+            };
+            assert_eq!(verificar(&agenda, "10/01/2025", "14:30", 15), vec![reuniao]);
+            assert_eq!(verificar(&agenda, "10/01/2025", "13:00", 90), vec![reuniao]);
+            assert!(verificar(&agenda, "10/01/2025", "15:00", 30).is_empty());
+            assert!(verificar(&agenda, "10/01/2025", "13:00", 60).is_empty());
+            // Compromisso que atravessa a meia-noite
+            assert_eq!(verificar(&agenda, "11/01/2025", "01:00", 30), vec![plantao]);
+            // A remarcação do próprio compromisso não conflita com ele mesmo
+            assert!(agenda
+                .verificar_conflitos(
+                    "10/01/2025".to_string(),
+                    "14:30".to_string(),
+                    60,
+                    Some(reuniao)
+                )
+                .unwrap()
+                .is_empty());
+
+            // Por padrão, sobreposições são permitidas sem aviso
+            let sobreposto = agenda
+                .criar_compromisso(
+                    "Café".to_string(),
+                    "10/01/2025".to_string(),
+                    "14:15".to_string(),
+                    Prioridade::Baixa,
+                    15,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            assert_eq!(ink::env::test::recorded_events().count(), 0);
+
+            // Os conflitos são retornados ao chamador e emitidos em evento
+            agenda.definir_politica_conflito(Politica::Avisar).unwrap();
+            let avisos = agenda
+                .atualizar_compromisso(
+                    sobreposto,
+                    NovoCompromisso {
+                        titulo: "Café".to_string(),
+                        data: "10/01/2025".to_string(),
+                        hora: "14:45".to_string(),
+                        prioridade: Prioridade::Baixa,
+                        duracao: 15,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(avisos.conflitos, vec![reuniao]);
+            assert!(!avisos.fora_do_expediente);
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+            let (_, avisos) = agenda
+                .criar_compromisso(
+                    "Ligação".to_string(),
+                    "11/01/2025".to_string(),
+                    "00:30".to_string(),
+                    Prioridade::Baixa,
+                    15,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap();
+            assert_eq!(avisos.conflitos, vec![plantao]);
+            assert_eq!(ink::env::test::recorded_events().count(), 2);
+
+            agenda
+                .definir_politica_conflito(Politica::Rejeitar)
+                .unwrap();
+            assert!(agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "10/01/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Baixa,
+                    30,
+                    DetalhesCompromisso::default()
+                )
+                .is_err());
+            assert!(agenda
+                .atualizar_compromisso(
+                    sobreposto,
+                    NovoCompromisso {
+                        titulo: "Café".to_string(),
+                        data: "11/01/2025".to_string(),
+                        hora: "00:00".to_string(),
+                        prioridade: Prioridade::Baixa,
+                        duracao: 15,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .is_err());
+            assert!(agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "10/01/2025".to_string(),
+                    "12:00".to_string(),
+                    Prioridade::Baixa,
+                    60,
+                    DetalhesCompromisso::default()
+                )
+                .is_ok());
+        }
+
+        fn timestamp(data: &str, hora: &str) -> Timestamp {
+            let (d, m, a) = Agenda::ler_data(data).unwrap();
+            let minutos = Agenda::dias_desde_epoch(d, m, a) as u64 * MINUTOS_POR_DIA as u64
+                + Agenda::ler_hora(hora).unwrap() as u64;
+            minutos * MS_POR_MINUTO
+        }
+
+        #[ink::test]
+        fn test_livre_ocupado() {
+            let mut agenda = Agenda::new();
+
+            for (hora, duracao) in [("09:00", 60), ("09:30", 60), ("11:00", 30), ("23:00", 120)] {
+                agenda
+                    .criar_compromisso(
+                        "Privado".to_string(),
+                        "10/01/2025".to_string(),
+                        hora.to_string(),
+                        Prioridade::Media,
+                        duracao,
+                        DetalhesCompromisso::default(),
+                    )
+                    .unwrap();
             }
 
-            if !Self::validar_hora(&hora) {
-                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
+            let inicio = timestamp("10/01/2025", "09:15");
+            let fim = timestamp("11/01/2025", "00:00");
+            let ocupados = agenda.livre_ocupado(inicio, fim).unwrap();
+            assert_eq!(
+                ocupados,
+                vec![
+                    // Sobrepostos são mesclados e recortados ao início da consulta
+                    Intervalo {
+                        inicio,
+                        fim: timestamp("10/01/2025", "10:30"),
+                    },
+                    Intervalo {
+                        inicio: timestamp("10/01/2025", "11:00"),
+                        fim: timestamp("10/01/2025", "11:30"),
+                    },
+                    // E também ao fim da consulta
+                    Intervalo {
+                        inicio: timestamp("10/01/2025", "23:00"),
+                        fim,
+                    },
+                ]
+            );
+
+            assert!(agenda.livre_ocupado(fim, inicio).is_err());
+
+            // Com a disponibilidade privada, apenas o dono consulta
+            agenda.definir_disponibilidade_publica(false).unwrap();
+            assert!(agenda.livre_ocupado(inicio, fim).is_ok());
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.livre_ocupado(inicio, fim).is_err());
+        }
+
+        #[ink::test]
+        fn test_proximo_horario_livre() {
+            let mut agenda = Agenda::new();
+
+            for (hora, duracao) in [("09:00", 60), ("10:30", 30), ("12:00", 60)] {
+                agenda
+                    .criar_compromisso(
+                        "Ocupado".to_string(),
+                        "10/01/2025".to_string(),
+                        hora.to_string(),
+                        Prioridade::Media,
+                        duracao,
+                        DetalhesCompromisso::default(),
+                    )
+                    .unwrap();
             }
 
-            let id = self.next_compromisso_id;
-            let compromisso = Compromisso {
-                titulo,
-                data,
-                hora,
-                prioridade,
-                duracao,
-            };
-            self.next_compromisso_id = self.next_compromisso_id.checked_add(1).expect("Overflow");
-            self.compromissos.insert(id, &compromisso);
-            Ok(id)
-        }
+            let intervalo = |de: &str, ate: &str| Intervalo {
+                inicio: timestamp("10/01/2025", de),
+                fim: timestamp("10/01/2025", ate),
+            };
+            let a_partir_de = timestamp("10/01/2025", "09:00");
+
+            // Primeiro horário de 45 minutos: o buraco das 10:00 às 10:30 é curto
+            let livres = agenda
+                .proximo_horario_livre(45, a_partir_de, 8 * 60, OpcoesBusca::default())
+                .unwrap();
+            assert_eq!(livres, vec![intervalo("11:00", "11:45")]);
+
+            // Vários horários, do mais cedo para o mais tarde
+            let opcoes = OpcoesBusca {
+                max_resultados: 3,
+                ..Default::default()
+            };
+            let livres = agenda
+                .proximo_horario_livre(30, a_partir_de, 8 * 60, opcoes)
+                .unwrap();
+            assert_eq!(
+                livres,
+                vec![
+                    intervalo("10:00", "10:30"),
+                    intervalo("11:00", "11:30"),
+                    intervalo("11:30", "12:00"),
+                ]
+            );
+
+            // Com 15 minutos de folga entre reuniões
+            let opcoes = OpcoesBusca {
+                intervalo_minimo: 15,
+                max_resultados: 2,
+                ..Default::default()
+            };
+            let livres = agenda
+                .proximo_horario_livre(30, a_partir_de, 8 * 60, opcoes)
+                .unwrap();
+            assert_eq!(
+                livres,
+                vec![intervalo("11:15", "11:45"), intervalo("13:15", "13:45")]
+            );
+
+            // Nada cabe na janela
+            assert!(agenda
+                .proximo_horario_livre(45, a_partir_de, 90, OpcoesBusca::default())
+                .unwrap()
+                .is_empty());
+            assert!(agenda
+                .proximo_horario_livre(0, a_partir_de, 90, OpcoesBusca::default())
+                .is_err());
+        }
+
+        #[ink::test]
+        fn test_expediente() {
+            let mut agenda = Agenda::new();
+            assert_eq!(
+                Agenda::dia_da_semana(Agenda::dias_desde_epoch(10, 1, 2025)),
+                DiaSemana::Sexta
+            );
+
+            agenda
+                .definir_expediente(DiaSemana::Sexta, "09:00".to_string(), "18:00".to_string())
+                .unwrap();
+            assert!(agenda
+                .definir_expediente(DiaSemana::Segunda, "18:00".to_string(), "09:00".to_string())
+                .is_err());
+            assert_eq!(agenda.listar_expediente().len(), 1);
+
+            let criar = |agenda: &mut Agenda, data: &str, hora: &str, duracao: i32| {
+                agenda.criar_compromisso(
+                    "Reunião".to_string(),
+                    data.to_string(),
+                    hora.to_string(),
+                    Prioridade::Media,
+                    duracao,
+                    DetalhesCompromisso::default(),
+                )
+            };
+
+            // Por padrão, horários fora do expediente são aceitos sem aviso
+            assert!(criar(&mut agenda, "11/01/2025", "03:00", 60).is_ok());
+            assert_eq!(ink::env::test::recorded_events().count(), 0);
+
+            agenda
+                .definir_politica_expediente(Politica::Avisar)
+                .unwrap();
+            assert!(criar(&mut agenda, "10/01/2025", "17:30", 60).is_ok());
+            assert_eq!(ink::env::test::recorded_events().count(), 1);
+
+            agenda
+                .definir_politica_expediente(Politica::Rejeitar)
+                .unwrap();
+            assert!(criar(&mut agenda, "10/01/2025", "08:00", 30).is_err());
+            assert!(criar(&mut agenda, "11/01/2025", "10:00", 30).is_err());
+            assert!(criar(&mut agenda, "10/01/2025", "09:00", 60).is_ok());
+
+            // A busca de horários pode se limitar ao expediente
+            let opcoes = OpcoesBusca {
+                max_resultados: 2,
+                apenas_expediente: true,
+                ..Default::default()
+            };
+            let livres = agenda
+                .proximo_horario_livre(60, timestamp("09/01/2025", "20:00"), 24 * 60, opcoes)
+                .unwrap();
+            assert_eq!(
+                livres,
+                vec![
+                    Intervalo {
+                        inicio: timestamp("10/01/2025", "10:00"),
+                        fim: timestamp("10/01/2025", "11:00"),
+                    },
+                    Intervalo {
+                        inicio: timestamp("10/01/2025", "11:00"),
+                        fim: timestamp("10/01/2025", "12:00"),
+                    },
+                ]
+            );
+
+            assert!(agenda.remover_expediente(DiaSemana::Sexta).unwrap());
+            assert!(criar(&mut agenda, "11/01/2025", "10:00", 30).is_ok());
+        }
+
+        #[ink::test]
+        fn test_participantes() {
+            let mut agenda = Agenda::new();
+
+            let ana = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "111111111".to_string(),
+                    30,
+                    "01/01/1995".to_string(),
+                    Categoria::Colega,
+                )
+                .unwrap();
+            let bruno = agenda
+                .criar_contato(
+                    "Bruno".to_string(),
+                    "222222222".to_string(),
+                    30,
+                    "01/01/1995".to_string(),
+                    Categoria::Colega,
+                )
+                .unwrap();
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            let almoco = agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "10/01/2025".to_string(),
+                    "12:00".to_string(),
+                    Prioridade::Baixa,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+
+            assert_eq!(agenda.adicionar_participante(reuniao, ana), Ok(true));
+            assert_eq!(agenda.adicionar_participante(reuniao, ana), Ok(false));
+            assert_eq!(agenda.adicionar_participante(reuniao, bruno), Ok(true));
+            assert_eq!(agenda.adicionar_participante(almoco, ana), Ok(true));
+            assert!(agenda.adicionar_participante(reuniao, 99).is_err());
+            assert!(agenda.adicionar_participante(99, ana).is_err());
+
+            let da_ana: Vec<u32> = agenda
+                .compromissos_do_contato(ana)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            assert_eq!(da_ana, vec![reuniao, almoco]);
+
+            let filtro = FiltroCompromissos {
+                participante: Some(bruno),
+                ..Default::default()
+            };
+            let pagina = agenda.filtrar_compromissos(filtro, 0, 10).unwrap();
+            assert_eq!(pagina.total, 1);
+            assert_eq!(pagina.itens[0].0, reuniao);
+
+            assert_eq!(agenda.remover_participante(almoco, ana), Ok(true));
+            assert_eq!(agenda.remover_participante(almoco, ana), Ok(false));
+            assert_eq!(agenda.compromissos_do_contato(ana).len(), 1);
+
+            // Excluir o contato o retira dos compromissos
+            agenda.deletar_contato(ana);
+            let compromisso = agenda.ler_compromisso(reuniao).unwrap();
+            assert_eq!(compromisso.participantes, vec![bruno]);
+            assert!(agenda.compromissos_do_contato(ana).is_empty());
+
+            // Excluir o compromisso limpa o índice reverso
+            agenda.deletar_compromisso(reuniao);
+            assert!(agenda.compromissos_do_contato(bruno).is_empty());
+        }
+
+        #[ink::test]
+        fn test_convites() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut agenda = Agenda::new();
+
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+
+            assert_eq!(agenda.convidar(reuniao, accounts.bob), Ok(true));
+            assert_eq!(agenda.convidar(reuniao, accounts.bob), Ok(false));
+            assert_eq!(agenda.convidar(reuniao, accounts.charlie), Ok(true));
+            assert!(agenda.convidar(99, accounts.bob).is_err());
+
+            // O convidado vê o convite na sua agenda e responde
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.convidar(reuniao, accounts.django).is_err());
+            let convites = agenda.meus_convites();
+            assert_eq!(convites.len(), 1);
+            assert_eq!(convites[0].compromisso_id, reuniao);
+            assert_eq!(convites[0].compromisso.titulo, "Reunião");
+            assert_eq!(convites[0].resposta, RespostaConvite::Pendente);
+
+            assert!(agenda
+                .responder_convite(reuniao, RespostaConvite::Pendente)
+                .is_err());
+            agenda
+                .responder_convite(reuniao, RespostaConvite::Aceito)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            agenda
+                .responder_convite(reuniao, RespostaConvite::Talvez)
+                .unwrap();
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.django);
+            assert!(agenda.meus_convites().is_empty());
+            assert!(agenda
+                .responder_convite(reuniao, RespostaConvite::Aceito)
+                .is_err());
+
+            // O organizador acompanha as respostas
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(
+                agenda.convites_do_compromisso(reuniao).unwrap(),
+                vec![
+                    Convite {
+                        convidado: accounts.bob,
+                        resposta: RespostaConvite::Aceito,
+                    },
+                    Convite {
+                        convidado: accounts.charlie,
+                        resposta: RespostaConvite::Talvez,
+                    },
+                ]
+            );
+            // Dois convites enviados e duas respostas
+            assert_eq!(ink::env::test::recorded_events().count(), 4);
+
+            assert_eq!(agenda.cancelar_convite(reuniao, accounts.charlie), Ok(true));
+            agenda.deletar_compromisso(reuniao);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.meus_convites().is_empty());
+        }
+
+        #[ink::test]
+        fn test_reservas() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(1, 1, 2025);
+
+            let disponibilidade = agenda
+                .publicar_disponibilidade(
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    "11:00".to_string(),
+                    30,
+                    0,
+                )
+                .unwrap();
+            assert!(agenda
+                .publicar_disponibilidade(
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    "09:20".to_string(),
+                    30,
+                    0,
+                )
+                .is_err());
+
+            // Um compromisso já existente ocupa um dos horários
+            agenda
+                .criar_compromisso(
+                    "Particular".to_string(),
+                    "10/01/2025".to_string(),
+                    "10:00".to_string(),
+                    Prioridade::Alta,
+                    30,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap();
+            agenda
+                .bloquear_horario(disponibilidade, "10:30".to_string())
+                .unwrap();
+            assert_eq!(
+                agenda.horarios_disponiveis(disponibilidade).unwrap(),
+                vec!["09:00", "09:30"]
+            );
+
+            // Outra conta reserva um horário
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda
+                .bloquear_horario(disponibilidade, "09:00".to_string())
+                .is_err());
+            let reserva_id = agenda
+                .reservar_horario(disponibilidade, "09:30".to_string(), "Consulta".to_string())
+                .unwrap();
+            let reserva = agenda.ler_reserva(reserva_id).unwrap();
+            assert_eq!(reserva.reservante, accounts.bob);
+            let compromisso = agenda.ler_compromisso(reserva.compromisso_id).unwrap();
+            assert_eq!(compromisso.data, "10/01/2025");
+            assert_eq!(compromisso.hora, "09:30");
+            assert_eq!(compromisso.duracao, 30);
+            assert_eq!(agenda.minhas_reservas(), vec![(reserva_id, reserva)]);
+
+            // Reserva dupla é impossível
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.charlie);
+            assert!(agenda
+                .reservar_horario(disponibilidade, "09:30".to_string(), "Consulta".to_string())
+                .is_err());
+            assert!(agenda
+                .reservar_horario(disponibilidade, "10:00".to_string(), "Consulta".to_string())
+                .is_err());
+            assert!(agenda
+                .reservar_horario(disponibilidade, "10:30".to_string(), "Consulta".to_string())
+                .is_err());
+            assert!(agenda
+                .reservar_horario(disponibilidade, "09:15".to_string(), "Consulta".to_string())
+                .is_err());
+            assert!(agenda.minhas_reservas().is_empty());
+            assert_eq!(
+                agenda.horarios_disponiveis(disponibilidade).unwrap(),
+                vec!["09:00"]
+            );
+
+            // O dono libera o horário bloqueado
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            agenda
+                .liberar_horario(disponibilidade, "10:30".to_string())
+                .unwrap();
+            assert_eq!(
+                agenda.horarios_disponiveis(disponibilidade).unwrap(),
+                vec!["09:00", "10:30"]
+            );
 
-        /// Lê um compromisso da agenda.
-        #[ink(message)]
-        pub fn ler_compromisso(&self, id: u32) -> Option<Compromisso> {
-            self.compromissos.get(id)
+            // Com a agenda privada, só o dono vê os horários livres
+            agenda.definir_disponibilidade_publica(false).unwrap();
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.horarios_disponiveis(disponibilidade).is_err());
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert!(agenda.horarios_disponiveis(disponibilidade).is_ok());
+
+            // Horários passados não podem ser reservados
+            definir_data_do_bloco(11, 1, 2025);
+            assert!(agenda
+                .horarios_disponiveis(disponibilidade)
+                .unwrap()
+                .is_empty());
         }
 
-        /// Atualiza um compromisso da agenda.
-        #[ink(message)]
-        pub fn atualizar_compromisso(
-            &mut self,
-            id: u32,
-            titulo: String,
-            data: String,
-            hora: String,
-            prioridade: Prioridade,
-            duracao: i32,
-        ) -> Result<bool, String> {
-            if titulo.is_empty() {
-                return Err("Título não pode estar vazio".to_string());
-            }
+        #[ink::test]
+        fn test_reservas_pagas() {
+            type E = ink::env::DefaultEnvironment;
+            let accounts = ink::env::test::default_accounts::<E>();
+            let saldo = |conta| ink::env::test::get_account_balance::<E>(conta).unwrap();
+            // O contrato guarda o valor pago em cada reserva
+            ink::env::test::set_callee::<E>(accounts.frank);
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(1, 1, 2025);
 
-            if !Self::validar_data(&data) {
-                return Err("Data inválida. O formato deve ser dd/mm/aaaa.".to_string());
-            }
+            agenda
+                .definir_politica_cancelamento(PoliticaCancelamento {
+                    antecedencia_minima: 24 * 60,
+                    percentual_tardio: 50,
+                })
+                .unwrap();
+            let disponibilidade = agenda
+                .publicar_disponibilidade(
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    "10:00".to_string(),
+                    30,
+                    100,
+                )
+                .unwrap();
 
-            if !Self::validar_hora(&hora) {
-                return Err("Hora inválida. O formato deve ser hh:mm.".to_string());
-            }
+            ink::env::test::set_account_balance::<E>(accounts.frank, 10_000_000);
+            ink::env::test::set_caller::<E>(accounts.bob);
+            ink::env::test::set_value_transferred::<E>(50);
+            assert!(agenda
+                .reservar_horario(disponibilidade, "09:00".to_string(), "Consulta".to_string())
+                .is_err());
+            ink::env::test::set_value_transferred::<E>(100);
+            let primeira = agenda
+                .reservar_horario(disponibilidade, "09:00".to_string(), "Consulta".to_string())
+                .unwrap();
+            let segunda = agenda
+                .reservar_horario(disponibilidade, "09:30".to_string(), "Retorno".to_string())
+                .unwrap();
+            ink::env::test::set_value_transferred::<E>(0);
+
+            // Ninguém recebe antes do fim do compromisso
+            assert!(agenda.liberar_pagamento(primeira).is_err());
+
+            // Cancelamento tardio reembolsa metade ao reservante
+            definir_data_do_bloco(10, 1, 2025);
+            let (bob, alice) = (saldo(accounts.bob), saldo(accounts.alice));
+            assert_eq!(agenda.cancelar_reserva(segunda), Ok(50));
+            assert_eq!(saldo(accounts.bob), bob + 50);
+            assert_eq!(saldo(accounts.alice), alice + 50);
+            assert_eq!(
+                agenda.ler_reserva(segunda).unwrap().situacao,
+                SituacaoReserva::Cancelada
+            );
+            assert!(agenda.cancelar_reserva(segunda).is_err());
+            assert_eq!(
+                agenda.horarios_disponiveis(disponibilidade).unwrap(),
+                vec!["09:30"]
+            );
 
-            if let Some(mut compromisso) = self.compromissos.get(id) {
-                compromisso.titulo = titulo;
-                compromisso.data = data;
-                compromisso.hora = hora;
-                compromisso.prioridade = prioridade;
-                compromisso.duracao = duracao;
-                self.compromissos.insert(id, &compromisso);
-                Ok(true)
-            } else {
-                Err("Compromisso não encontrado".to_string())
-            }
+            // Terminado o compromisso, o valor é repassado ao dono
+            ink::env::test::set_block_timestamp::<E>(timestamp("10/01/2025", "09:30"));
+            assert!(agenda.cancelar_reserva(primeira).is_err());
+            ink::env::test::set_caller::<E>(accounts.charlie);
+            let alice = saldo(accounts.alice);
+            assert_eq!(agenda.liberar_pagamento(primeira), Ok(100));
+            assert_eq!(saldo(accounts.alice), alice + 100);
+            assert!(agenda.liberar_pagamento(primeira).is_err());
         }
 
-        /// Deleta um compromisso da agenda.
-        #[ink(message)]
-        pub fn deletar_compromisso(&mut self, id: u32) -> bool {
-            if self.compromissos.contains(id) {
-                self.compromissos.remove(id);
-                true
-            } else {
-                false
-            }
-        }
+        #[ink::test]
+        fn test_cancelamento_de_reserva_com_antecedencia() {
+            type E = ink::env::DefaultEnvironment;
+            let accounts = ink::env::test::default_accounts::<E>();
+            ink::env::test::set_callee::<E>(accounts.frank);
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(1, 1, 2025);
+
+            let disponibilidade = agenda
+                .publicar_disponibilidade(
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    "10:00".to_string(),
+                    60,
+                    100,
+                )
+                .unwrap();
+            ink::env::test::set_account_balance::<E>(accounts.frank, 10_000_000);
 
-        /// Lista todos os compromissos da agenda.
-        #[ink(message)]
-        pub fn listar_compromissos(&self) -> Vec<Compromisso> {
-            let mut lista = Vec::new();
-            for id in 0..self.next_compromisso_id {
-                if let Some(compromisso) = self.compromissos.get(id) {
-                    lista.push(compromisso);
-                }
-            }
-            lista
-        }
-    }
+            ink::env::test::set_caller::<E>(accounts.bob);
+            ink::env::test::set_value_transferred::<E>(100);
+            let reserva = agenda
+                .reservar_horario(disponibilidade, "09:00".to_string(), "Consulta".to_string())
+                .unwrap();
+            ink::env::test::set_value_transferred::<E>(0);
+            let compromisso_id = agenda.ler_reserva(reserva).unwrap().compromisso_id;
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
+            ink::env::test::set_caller::<E>(accounts.charlie);
+            assert!(agenda.cancelar_reserva(reserva).is_err());
+
+            // Com a antecedência mínima, o reembolso é integral
+            ink::env::test::set_caller::<E>(accounts.bob);
+            assert_eq!(agenda.cancelar_reserva(reserva), Ok(100));
+            let compromisso = agenda.ler_compromisso(compromisso_id).unwrap();
+            assert_eq!(compromisso.status, StatusCompromisso::Cancelado);
+        }
 
         #[ink::test]
-        fn test_criar_contato() {
+        fn test_lembretes() {
             let mut agenda = Agenda::new();
 
-            // Teste criando um contato válido
-            let nome = "John Doe".to_string();
-            let telefone = "123456789".to_string();
-            let idade = 30;
-            let data_aniversario = "01/01/1990".to_string();
-            let categoria = Categoria::Amigo;
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "14:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            let almoco = agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "10/01/2025".to_string(),
+                    "12:00".to_string(),
+                    Prioridade::Baixa,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            agenda
+                .definir_lembretes(reuniao, vec![15, 1440, 15])
+                .unwrap();
+            agenda.definir_lembretes(almoco, vec![30]).unwrap();
+            assert_eq!(
+                agenda.ler_compromisso(reuniao).unwrap().lembretes,
+                vec![15, 1440]
+            );
+            assert!(agenda.definir_lembretes(reuniao, vec![31 * 1440]).is_err());
 
-            let result = agenda.criar_contato(
-                nome.clone(),
-                telefone.clone(),
-                idade,
-                data_aniversario.clone(),
-                categoria.clone(),
+            // Na véspera, só o lembrete de um dia antes está para disparar
+            let pendentes = agenda
+                .lembretes_pendentes(timestamp("09/01/2025", "13:00"), 120)
+                .unwrap();
+            assert_eq!(pendentes.len(), 1);
+            assert_eq!(pendentes[0].compromisso_id, reuniao);
+            assert_eq!(pendentes[0].antecedencia, 1440);
+            assert_eq!(pendentes[0].disparo, timestamp("09/01/2025", "14:00"));
+            assert_eq!(pendentes[0].inicio, timestamp("10/01/2025", "14:00"));
+
+            // No dia, em ordem de disparo, incluindo o que não foi confirmado
+            let agora = timestamp("10/01/2025", "11:00");
+            let pendentes: Vec<(u32, u32)> = agenda
+                .lembretes_pendentes(agora, 4 * 60)
+                .unwrap()
+                .into_iter()
+                .map(|l| (l.compromisso_id, l.antecedencia))
+                .collect();
+            assert_eq!(
+                pendentes,
+                vec![(reuniao, 1440), (almoco, 30), (reuniao, 15)]
             );
-            assert!(result.is_ok(), "Falha ao criar contato");
 
-            let id = result.unwrap();
-            let contato = agenda.ler_contato(id).expect("O contato deve existir");
+            // Lembretes confirmados não são entregues de novo
+            agenda.confirmar_lembrete(reuniao, 1440).unwrap();
+            agenda.confirmar_lembrete(almoco, 30).unwrap();
+            assert!(agenda.confirmar_lembrete(almoco, 45).is_err());
+            let pendentes = agenda.lembretes_pendentes(agora, 4 * 60).unwrap();
+            assert_eq!(pendentes.len(), 1);
+            assert_eq!(pendentes[0].antecedencia, 15);
+
+            // Ao remarcar, os lembretes voltam a ficar pendentes
+            agenda
+                .atualizar_compromisso(
+                    almoco,
+                    NovoCompromisso {
+                        titulo: "Almoço".to_string(),
+                        data: "10/01/2025".to_string(),
+                        hora: "12:30".to_string(),
+                        prioridade: Prioridade::Baixa,
+                        duracao: 60,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .unwrap();
+            let pendentes = agenda.lembretes_pendentes(agora, 4 * 60).unwrap();
+            assert_eq!(pendentes.len(), 2);
 
-            assert_eq!(contato.nome, nome);
-            assert_eq!(contato.telefone, telefone);
-            assert_eq!(contato.idade, idade);
-            assert_eq!(contato.data_aniversario, data_aniversario);
-            assert_eq!(contato.categoria, categoria);
+            // Instantes no limite do timestamp não estouram
+            assert_eq!(
+                agenda.lembretes_pendentes(Timestamp::MAX - MS_POR_MINUTO, 10),
+                Ok(Vec::new())
+            );
+            assert!(agenda.lembretes_pendentes(Timestamp::MAX, 10).is_err());
         }
 
         #[ink::test]
-        fn test_criar_contato_data_invalida() {
+        fn test_status_do_compromisso() {
             let mut agenda = Agenda::new();
 
-            // Teste criando um contato com data inválida
-            let nome = "John Doe".to_string();
-            let telefone = "123456789".to_string();
-            let idade = 30;
-            let data_aniversario = "32/13/1990".to_string(); // Data inválida
-            let categoria = Categoria::Amigo;
+            let criar = |agenda: &mut Agenda, titulo: &str, hora: &str| {
+                agenda
+                    .criar_compromisso(
+                        titulo.to_string(),
+                        "10/01/2025".to_string(),
+                        hora.to_string(),
+                        Prioridade::Media,
+                        60,
+                        DetalhesCompromisso::default(),
+                    )
+                    .unwrap()
+                    .0
+            };
+            let reuniao = criar(&mut agenda, "Reunião", "09:00");
+            let almoco = criar(&mut agenda, "Almoço", "12:00");
+            assert_eq!(
+                agenda.ler_compromisso(reuniao).unwrap().status,
+                StatusCompromisso::Agendado
+            );
 
-            let result = agenda.criar_contato(nome, telefone, idade, data_aniversario, categoria);
-            assert!(
-                result.is_err(),
-                "Contato não deve ser criado com data inválida"
+            // Transições válidas
+            agenda.confirmar_compromisso(reuniao, None).unwrap();
+            agenda.iniciar_compromisso(reuniao, None).unwrap();
+            agenda
+                .concluir_compromisso(reuniao, Some("Pauta encerrada".to_string()))
+                .unwrap();
+            let compromisso = agenda.ler_compromisso(reuniao).unwrap();
+            assert_eq!(compromisso.status, StatusCompromisso::Concluido);
+            assert_eq!(
+                compromisso.motivo_status,
+                Some("Pauta encerrada".to_string())
+            );
+
+            // Concluído e cancelado são finais
+            assert!(agenda.cancelar_compromisso(reuniao, None).is_err());
+            agenda
+                .cancelar_compromisso(almoco, Some("Chuva".to_string()))
+                .unwrap();
+            assert!(agenda.confirmar_compromisso(almoco, None).is_err());
+            assert!(agenda.confirmar_compromisso(99, None).is_err());
+            assert_eq!(ink::env::test::recorded_events().count(), 4);
+
+            // Cancelados ficam fora das listagens por padrão
+            assert_eq!(agenda.listar_compromissos().len(), 1);
+            assert_eq!(
+                agenda.listar_compromissos_por_status(StatusCompromisso::Cancelado)[0].0,
+                almoco
+            );
+            let dia = agenda.agenda_do_dia("10/01/2025".to_string()).unwrap();
+            assert_eq!(dia.compromissos.len(), 1);
+            let pagina = agenda
+                .filtrar_compromissos(FiltroCompromissos::default(), 0, 10)
+                .unwrap();
+            assert_eq!(pagina.total, 1);
+            let filtro = FiltroCompromissos {
+                status: vec![StatusCompromisso::Cancelado],
+                ..Default::default()
+            };
+            assert_eq!(
+                agenda.filtrar_compromissos(filtro, 0, 10).unwrap().itens[0].0,
+                almoco
+            );
+
+            // E não ocupam mais o horário
+            assert!(agenda
+                .verificar_conflitos("10/01/2025".to_string(), "12:00".to_string(), 30, None)
+                .unwrap()
+                .is_empty());
+
+            let estatisticas = agenda
+                .estatisticas("01/01/2025".to_string(), "31/12/2025".to_string())
+                .unwrap();
+            assert_eq!(
+                estatisticas.compromissos_por_status,
+                vec![
+                    (StatusCompromisso::Agendado, 0),
+                    (StatusCompromisso::Confirmado, 0),
+                    (StatusCompromisso::EmAndamento, 0),
+                    (StatusCompromisso::Concluido, 1),
+                    (StatusCompromisso::Cancelado, 1),
+                ]
+            );
+
+            // Um cancelado pode ser editado mesmo com o antigo horário ocupado
+            criar(&mut agenda, "Visita", "12:00");
+            agenda
+                .definir_politica_conflito(Politica::Rejeitar)
+                .unwrap();
+            let avisos = agenda
+                .atualizar_compromisso(
+                    almoco,
+                    NovoCompromisso {
+                        titulo: "Almoço adiado".to_string(),
+                        data: "10/01/2025".to_string(),
+                        hora: "12:00".to_string(),
+                        prioridade: Prioridade::Media,
+                        duracao: 60,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(avisos, Avisos::default());
+            assert_eq!(
+                agenda.ler_compromisso(almoco).unwrap().titulo,
+                "Almoço adiado"
             );
         }
 
         #[ink::test]
-        fn test_atualizar_contato() {
+        fn test_telefones_emails_e_enderecos() {
             let mut agenda = Agenda::new();
-
-            // Cria um contato válido
-            let nome = "John Doe".to_string();
-            let telefone = "123456789".to_string();
-            let idade = 30;
-            let data_aniversario = "01/01/1990".to_string();
-            let categoria = Categoria::Amigo;
-
             let id = agenda
                 .criar_contato(
-                    nome.clone(),
-                    telefone.clone(),
-                    idade,
-                    data_aniversario.clone(),
-                    categoria,
+                    "Maria".to_string(),
+                    "(11) 98765-4321".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    Categoria::Amigo,
                 )
                 .unwrap();
 
-            // Atualiza o contato com novas informações
-            let new_nome = "Jane Doe".to_string();
-            let new_telefone = "987654321".to_string();
-            let new_idade = 31;
-            let new_data_aniversario = "02/02/1990".to_string();
-            let new_categoria = Categoria::Familiar;
-
-            let update_result = agenda.atualizar_contato(
-                id,
-                new_nome.clone(),
-                new_telefone.clone(),
-                new_idade,
-                new_data_aniversario.clone(),
-                new_categoria.clone(),
+            // Telefones
+            let trabalho = |numero: &str, principal: bool| Telefone {
+                rotulo: Rotulo::Trabalho,
+                numero: numero.to_string(),
+                principal,
+            };
+            agenda
+                .adicionar_telefone(id, trabalho("11 3333-4444", false))
+                .unwrap();
+            assert!(agenda
+                .adicionar_telefone(id, trabalho("1133334444", false))
+                .is_err());
+            assert!(agenda
+                .adicionar_telefone(id, trabalho("abc", false))
+                .is_err());
+            assert!(agenda
+                .adicionar_telefone(99, trabalho("123", false))
+                .is_err());
+            assert_eq!(
+                agenda.contato_por_telefone("1133334444".to_string())[0].0,
+                id
             );
-            assert!(update_result.is_ok(), "Falha ao atualizar contato");
 
-            let updated_contato = agenda.ler_contato(id).expect("O contato deve existir");
-            assert_eq!(updated_contato.nome, new_nome);
-            assert_eq!(updated_contato.telefone, new_telefone);
-            assert_eq!(updated_contato.idade, new_idade);
-            assert_eq!(updated_contato.data_aniversario, new_data_aniversario);
-            assert_eq!(updated_contato.categoria, new_categoria);
+            agenda
+                .adicionar_telefone(id, trabalho("2222", true))
+                .unwrap();
+            let contato = agenda.ler_contato(id).unwrap();
+            assert_eq!(contato.telefone, "2222");
+            assert_eq!(contato.telefones.iter().filter(|t| t.principal).count(), 1);
+
+            agenda.definir_telefone_principal(id, 0).unwrap();
+            assert_eq!(agenda.ler_contato(id).unwrap().telefone, "11987654321");
+            agenda.remover_telefone(id, 0).unwrap();
+            let contato = agenda.ler_contato(id).unwrap();
+            assert_eq!(contato.telefone, "1133334444");
+            assert!(agenda
+                .contato_por_telefone("11987654321".to_string())
+                .is_empty());
+            agenda.remover_telefone(id, 1).unwrap();
+            assert!(agenda.remover_telefone(id, 0).is_err());
+            assert!(agenda.remover_telefone(id, 5).is_err());
+
+            // Com telefone único, números secundários também são verificados
+            agenda.definir_telefone_unico(true).unwrap();
+            assert!(agenda
+                .criar_contato(
+                    "João".to_string(),
+                    "1133334444".to_string(),
+                    40,
+                    "02/02/1984".to_string(),
+                    Categoria::Colega,
+                )
+                .is_err());
+
+            // E-mails
+            let email = |endereco: &str| Email {
+                rotulo: Rotulo::Casa,
+                endereco: endereco.to_string(),
+                principal: false,
+            };
+            agenda
+                .adicionar_email(id, email("maria@Exemplo.COM"))
+                .unwrap();
+            for invalido in [
+                "maria",
+                "@exemplo.com",
+                "maria@exemplo",
+                "ma ria@x.com",
+                "a@b..com",
+            ] {
+                assert!(agenda.adicionar_email(id, email(invalido)).is_err());
+            }
+            assert!(agenda
+                .adicionar_email(id, email("MARIA@exemplo.com"))
+                .is_err());
+            agenda.adicionar_email(id, email("m@trabalho.com")).unwrap();
+            let contato = agenda.ler_contato(id).unwrap();
+            assert_eq!(contato.emails[0].endereco, "maria@exemplo.com");
+            assert!(contato.emails[0].principal);
+            assert!(!contato.emails[1].principal);
+            agenda.remover_email(id, 0).unwrap();
+            assert!(agenda.ler_contato(id).unwrap().emails[0].principal);
+
+            // Endereços
+            let endereco = Endereco {
+                rotulo: Rotulo::Casa,
+                logradouro: "Rua A, 10".to_string(),
+                cidade: "São Paulo".to_string(),
+                estado: "SP".to_string(),
+                cep: "01000-000".to_string(),
+                principal: false,
+            };
+            agenda.adicionar_endereco(id, endereco.clone()).unwrap();
+            assert!(agenda
+                .adicionar_endereco(
+                    id,
+                    Endereco {
+                        cidade: String::new(),
+                        ..endereco.clone()
+                    }
+                )
+                .is_err());
+            agenda.definir_endereco_principal(id, 0).unwrap();
+            assert!(agenda.definir_endereco_principal(id, 1).is_err());
+            agenda.remover_endereco(id, 0).unwrap();
+            assert!(agenda.ler_contato(id).unwrap().enderecos.is_empty());
         }
 
         #[ink::test]
-        fn test_atualizar_contato_data_invalida() {
+        fn test_categorias_personalizadas() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             let mut agenda = Agenda::new();
 
-            // Cria um contato válido
-            let nome = "John Doe".to_string();
-            let telefone = "123456789".to_string();
-            let idade = 30;
-            let data_aniversario = "01/01/1990".to_string();
-            let categoria = Categoria::Amigo;
+            let clientes = agenda
+                .criar_categoria("Clientes".to_string(), "#FFAA00".to_string())
+                .unwrap();
+            assert_eq!(clientes, Categoria::Personalizada(0));
+            assert!(agenda
+                .criar_categoria("clientes".to_string(), "#000000".to_string())
+                .is_err());
+            assert!(agenda
+                .criar_categoria("Amigo".to_string(), "#000000".to_string())
+                .is_err());
+            assert!(agenda
+                .criar_categoria("Médicos".to_string(), "vermelho".to_string())
+                .is_err());
+            let medicos = agenda
+                .criar_categoria("Médicos".to_string(), "#ff0000".to_string())
+                .unwrap();
+
+            let categorias = agenda.listar_categorias();
+            assert_eq!(categorias.len(), 5);
+            assert_eq!(categorias[3].nome, "Clientes");
+            assert_eq!(categorias[3].cor, "#ffaa00");
 
             let id = agenda
-                .criar_contato(nome, telefone, idade, data_aniversario, categoria)
+                .criar_contato(
+                    "Ana".to_string(),
+                    "123456789".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    clientes.clone(),
+                )
                 .unwrap();
+            assert!(agenda
+                .criar_contato(
+                    "Bia".to_string(),
+                    "987654321".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    Categoria::Personalizada(7),
+                )
+                .is_err());
+            assert_eq!(
+                agenda
+                    .estatisticas("01/01/2025".to_string(), "31/12/2025".to_string())
+                    .unwrap()
+                    .contatos_por_categoria[3],
+                (clientes.clone(), 1)
+            );
 
-            // Tenta atualizar com data inválida
-            let new_data_aniversario = "32/13/1990".to_string(); // Data inválida
-            let update_result = agenda.atualizar_contato(
-                id,
-                "Jane Doe".to_string(),
-                "987654321".to_string(),
-                31,
-                new_data_aniversario,
-                Categoria::Familiar,
+            // Categorias em uso não podem ser removidas
+            assert!(agenda.remover_categoria(0).is_err());
+            agenda
+                .atualizar_categoria(0, "Clientes VIP".to_string(), "#00ff00".to_string())
+                .unwrap();
+            assert!(agenda
+                .atualizar_categoria(0, "Médicos".to_string(), "#00ff00".to_string())
+                .is_err());
+            assert_eq!(agenda.remover_categoria(1), Ok(true));
+            assert_eq!(agenda.remover_categoria(1), Ok(false));
+            assert!(agenda
+                .atualizar_contato(
+                    id,
+                    "Ana".to_string(),
+                    "123456789".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    medicos,
+                )
+                .is_err());
+            agenda.deletar_contato(id);
+            assert_eq!(agenda.remover_categoria(0), Ok(true));
+            assert_eq!(agenda.listar_categorias().len(), 3);
+
+            // Apenas o dono gerencia categorias
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda
+                .criar_categoria("Fornecedores".to_string(), "#123456".to_string())
+                .is_err());
+        }
+
+        #[ink::test]
+        fn test_tags() {
+            let mut agenda = Agenda::new();
+            let contato = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "123456789".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            let almoco = agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "10/01/2025".to_string(),
+                    "12:00".to_string(),
+                    Prioridade::Baixa,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+
+            assert_eq!(
+                agenda.adicionar_tag_contato(contato, " Cliente-VIP ".to_string()),
+                Ok(true)
+            );
+            assert_eq!(
+                agenda.adicionar_tag_contato(contato, "cliente-vip".to_string()),
+                Ok(false)
+            );
+            assert!(agenda
+                .adicionar_tag_contato(contato, "com espaço".to_string())
+                .is_err());
+            assert!(agenda.adicionar_tag_contato(99, "x".to_string()).is_err());
+            agenda
+                .adicionar_tag_compromisso(reuniao, "projeto-x".to_string())
+                .unwrap();
+            agenda
+                .adicionar_tag_compromisso(reuniao, "urgente".to_string())
+                .unwrap();
+            agenda
+                .adicionar_tag_compromisso(almoco, "projeto-x".to_string())
+                .unwrap();
+            assert_eq!(
+                agenda.ler_compromisso(reuniao).unwrap().tags,
+                vec!["projeto-x", "urgente"]
             );
 
-            assert!(
-                update_result.is_err(),
-                "Contato não deve ser atualizado com data inválida"
+            assert_eq!(
+                agenda.contatos_com_tag("CLIENTE-VIP".to_string())[0].0,
+                contato
+            );
+            assert_eq!(
+                agenda.compromissos_com_tag("projeto-x".to_string()).len(),
+                2
+            );
+            assert_eq!(
+                agenda.tags_em_uso(),
+                vec![
+                    UsoDeTag {
+                        tag: "cliente-vip".to_string(),
+                        contatos: 1,
+                        compromissos: 0
+                    },
+                    UsoDeTag {
+                        tag: "projeto-x".to_string(),
+                        contatos: 0,
+                        compromissos: 2
+                    },
+                    UsoDeTag {
+                        tag: "urgente".to_string(),
+                        contatos: 0,
+                        compromissos: 1
+                    },
+                ]
+            );
+
+            // Filtro exige todas as tags
+            let filtro = FiltroCompromissos {
+                tags: vec!["Projeto-X".to_string(), "urgente".to_string()],
+                ..Default::default()
+            };
+            let pagina = agenda.filtrar_compromissos(filtro, 0, 10).unwrap();
+            assert_eq!(pagina.total, 1);
+            assert_eq!(pagina.itens[0].0, reuniao);
+
+            // Tags acompanham atualizações e somem quando não marcam mais nada
+            agenda
+                .atualizar_compromisso(
+                    reuniao,
+                    NovoCompromisso {
+                        titulo: "Reunião".to_string(),
+                        data: "11/01/2025".to_string(),
+                        hora: "10:00".to_string(),
+                        prioridade: Prioridade::Alta,
+                        duracao: 30,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(agenda.compromissos_com_tag("urgente".to_string()).len(), 1);
+            assert_eq!(
+                agenda.remover_tag_compromisso(reuniao, "urgente".to_string()),
+                Ok(true)
+            );
+            assert_eq!(
+                agenda.remover_tag_compromisso(reuniao, "urgente".to_string()),
+                Ok(false)
+            );
+            agenda.deletar_contato(contato);
+            agenda.deletar_compromisso(almoco);
+            assert_eq!(
+                agenda.tags_em_uso(),
+                vec![UsoDeTag {
+                    tag: "projeto-x".to_string(),
+                    contatos: 0,
+                    compromissos: 1
+                }]
             );
         }
 
         #[ink::test]
-        fn test_criar_compromisso() {
+        fn test_detalhes_do_compromisso() {
             let mut agenda = Agenda::new();
+            let local = Local {
+                texto: "Sala 3".to_string(),
+                coordenadas: Some(Coordenadas {
+                    latitude: -23_550_520,
+                    longitude: -46_633_308,
+                }),
+            };
+            let id = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso {
+                        descricao: Some("Revisar metas do trimestre".to_string()),
+                        local: Some(local.clone()),
+                        link_reuniao: Some("https://meet.exemplo.com/abc".to_string()),
+                    },
+                )
+                .unwrap()
+                .0;
+            let compromisso = agenda.ler_compromisso(id).unwrap();
+            assert_eq!(compromisso.detalhes.local, Some(local));
+            assert_eq!(
+                compromisso.detalhes.link_reuniao,
+                Some("https://meet.exemplo.com/abc".to_string())
+            );
 
-            // Teste criando um compromisso válido
-            let titulo = "Reunião".to_string();
-            let data = "01/01/2025".to_string();
-            let hora = "14:00".to_string();
-            let prioridade = Prioridade::Alta;
-            let duracao = 60;
-
-            let result = agenda.criar_compromisso(
-                titulo.clone(),
-                data.clone(),
-                hora.clone(),
-                prioridade.clone(),
-                duracao,
+            let criar = |agenda: &mut Agenda, local, link: &str| {
+                agenda.criar_compromisso(
+                    "Outro".to_string(),
+                    "11/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Baixa,
+                    30,
+                    DetalhesCompromisso {
+                        local,
+                        link_reuniao: Some(link.to_string()),
+                        ..Default::default()
+                    },
+                )
+            };
+            assert!(criar(&mut agenda, None, "meet.exemplo.com").is_err());
+            assert!(criar(&mut agenda, None, "https://").is_err());
+            assert!(criar(&mut agenda, None, "https://a b.com").is_err());
+            let fora_do_mapa = Local {
+                texto: "Polo".to_string(),
+                coordenadas: Some(Coordenadas {
+                    latitude: 91_000_000,
+                    longitude: 0,
+                }),
+            };
+            assert!(criar(&mut agenda, Some(fora_do_mapa), "http://x.com").is_err());
+            assert!(agenda
+                .criar_compromisso(
+                    "Longo".to_string(),
+                    "11/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Baixa,
+                    30,
+                    DetalhesCompromisso {
+                        descricao: Some("a".repeat(MAX_DESCRICAO + 1)),
+                        ..Default::default()
+                    },
+                )
+                .is_err());
+
+            // Atualizar substitui os detalhes, que também são validados
+            let dados = |link: &str| NovoCompromisso {
+                titulo: "Reunião".to_string(),
+                data: "10/01/2025".to_string(),
+                hora: "10:00".to_string(),
+                prioridade: Prioridade::Alta,
+                duracao: 60,
+                detalhes: DetalhesCompromisso {
+                    link_reuniao: Some(link.to_string()),
+                    ..Default::default()
+                },
+            };
+            assert!(agenda
+                .atualizar_compromisso(id, dados("meet.exemplo.com"))
+                .is_err());
+            assert_eq!(agenda.ler_compromisso(id).unwrap(), compromisso);
+            agenda
+                .atualizar_compromisso(id, dados("https://meet.exemplo.com/nova"))
+                .unwrap();
+            let atualizado = agenda.ler_compromisso(id).unwrap();
+            assert_eq!(atualizado.hora, "10:00");
+            assert_eq!(
+                atualizado.detalhes,
+                DetalhesCompromisso {
+                    link_reuniao: Some("https://meet.exemplo.com/nova".to_string()),
+                    ..Default::default()
+                }
             );
-            assert!(result.is_ok(), "Falha ao criar compromisso");
+        }
 
-            let id = result.unwrap();
+        #[ink::test]
+        fn test_grupos() {
+            let mut agenda = Agenda::new();
+            let contatos: Vec<u32> = ["Ana", "Bruno", "Carla"]
+                .iter()
+                .enumerate()
+                .map(|(i, nome)| {
+                    agenda
+                        .criar_contato(
+                            nome.to_string(),
+                            format!("11{i}"),
+                            30,
+                            "01/01/1994".to_string(),
+                            Categoria::Colega,
+                        )
+                        .unwrap()
+                })
+                .collect();
             let compromisso = agenda
-                .ler_compromisso(id)
-                .expect("O compromisso deve existir");
+                .criar_compromisso(
+                    "Reunião de vendas".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+
+            assert!(agenda.criar_grupo(" ".to_string()).is_err());
+            let vendas = agenda.criar_grupo("Time de Vendas".to_string()).unwrap();
+            assert_eq!(agenda.adicionar_ao_grupo(vendas, contatos[1]), Ok(true));
+            assert_eq!(agenda.adicionar_ao_grupo(vendas, contatos[0]), Ok(true));
+            assert_eq!(agenda.adicionar_ao_grupo(vendas, contatos[0]), Ok(false));
+            assert!(agenda.adicionar_ao_grupo(vendas, 99).is_err());
+            assert!(agenda.adicionar_ao_grupo(99, contatos[0]).is_err());
+            assert_eq!(
+                agenda.ler_grupo(vendas).unwrap().membros,
+                vec![contatos[0], contatos[1]]
+            );
+            let nomes: Vec<String> = agenda
+                .membros_do_grupo(vendas)
+                .unwrap()
+                .into_iter()
+                .map(|(_, c)| c.nome)
+                .collect();
+            assert_eq!(nomes, vec!["Ana", "Bruno"]);
+            assert_eq!(agenda.grupos_do_contato(contatos[0])[0].0, vendas);
+
+            // Grupo inteiro como participante, sem repetir quem já participa
+            agenda
+                .adicionar_participante(compromisso, contatos[0])
+                .unwrap();
+            assert_eq!(
+                agenda.adicionar_grupo_ao_compromisso(compromisso, vendas),
+                Ok(1)
+            );
+            assert_eq!(
+                agenda.adicionar_grupo_ao_compromisso(compromisso, vendas),
+                Ok(0)
+            );
+            assert_eq!(agenda.compromissos_do_contato(contatos[1]).len(), 1);
 
-            assert_eq!(compromisso.titulo, titulo);
-            assert_eq!(compromisso.data, data);
-            assert_eq!(compromisso.hora, hora);
-            assert_eq!(compromisso.prioridade, prioridade);
-            assert_eq!(compromisso.duracao, duracao);
+            // Contato removido sai do grupo
+            agenda.deletar_contato(contatos[0]);
+            assert_eq!(agenda.ler_grupo(vendas).unwrap().membros, vec![contatos[1]]);
+
+            agenda
+                .renomear_grupo(vendas, "Comercial".to_string())
+                .unwrap();
+            assert_eq!(agenda.listar_grupos()[0].1.nome, "Comercial");
+            assert_eq!(agenda.remover_do_grupo(vendas, contatos[2]), Ok(false));
+            assert!(agenda.remover_grupo(vendas));
+            assert!(!agenda.remover_grupo(vendas));
+            assert!(agenda.grupos_do_contato(contatos[1]).is_empty());
         }
 
         #[ink::test]
-        fn test_criar_compromisso_data_invalida() {
+        fn test_lixeira() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            definir_data_do_bloco(1, 1, 2025);
             let mut agenda = Agenda::new();
+            let ana = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "111".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            let grupo = agenda.criar_grupo("Amigos".to_string()).unwrap();
+            agenda.adicionar_participante(reuniao, ana).unwrap();
+            agenda.adicionar_ao_grupo(grupo, ana).unwrap();
+
+            // Excluir move para a lixeira e tira das listagens
+            assert!(agenda.deletar_contato(ana));
+            assert!(agenda.ler_contato(ana).is_none());
+            assert!(agenda.listar_contatos().is_empty());
+            assert!(agenda
+                .ler_compromisso(reuniao)
+                .unwrap()
+                .participantes
+                .is_empty());
+            let lixeira = agenda.lixeira_de_contatos();
+            assert_eq!(lixeira.len(), 1);
+            assert_eq!(lixeira[0].1.compromissos, vec![reuniao]);
+            assert_eq!(lixeira[0].1.grupos, vec![grupo]);
+
+            // Com telefone único, não restaura se o número foi reaproveitado
+            agenda.definir_telefone_unico(true).unwrap();
+            let outra = agenda
+                .criar_contato(
+                    "Outra".to_string(),
+                    "111".to_string(),
+                    20,
+                    "02/02/2004".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            assert!(agenda.restaurar_contato(ana).is_err());
+            agenda.deletar_contato(outra);
+
+            agenda.restaurar_contato(ana).unwrap();
+            assert_eq!(agenda.ler_contato(ana).unwrap().nome, "Ana");
+            assert_eq!(
+                agenda.ler_compromisso(reuniao).unwrap().participantes,
+                vec![ana]
+            );
+            assert_eq!(agenda.ler_grupo(grupo).unwrap().membros, vec![ana]);
+            assert_eq!(agenda.contato_por_telefone("111".to_string())[0].0, ana);
+            assert!(agenda.restaurar_contato(ana).is_err());
+            assert_eq!(agenda.lixeira_de_contatos().len(), 1);
+
+            // Compromissos
+            assert!(agenda.deletar_compromisso(reuniao));
+            assert!(agenda.listar_compromissos().is_empty());
+            assert!(agenda.compromissos_do_contato(ana).is_empty());
+            assert_eq!(agenda.lixeira_de_compromissos()[0].0, reuniao);
+            agenda.restaurar_compromisso(reuniao).unwrap();
+            assert_eq!(agenda.compromissos_do_contato(ana)[0].0, reuniao);
+
+            // Depois da retenção, os itens expiram e são apagados
+            agenda.definir_retencao_lixeira(2).unwrap();
+            assert!(agenda.deletar_compromisso(reuniao));
+            definir_data_do_bloco(3, 1, 2025);
+            assert!(agenda.lixeira_de_compromissos().is_empty());
+            assert!(agenda.restaurar_compromisso(reuniao).is_err());
+            assert!(agenda.deletar_contato(ana));
+            assert_eq!(agenda.lixeira_de_contatos().len(), 1);
+
+            // Esvaziar é exclusivo do dono
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.esvaziar_lixeira().is_err());
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert!(agenda.esvaziar_lixeira().unwrap() > 0);
+            assert_eq!(agenda.esvaziar_lixeira(), Ok(0));
+            assert!(agenda.lixeira_de_contatos().is_empty());
+            assert!(agenda.restaurar_contato(ana).is_err());
+        }
 
-            // Teste criando um compromisso com data inválida
-            let titulo = "Reunião".to_string();
-            let data = "32/13/2025".to_string(); // Data inválida
-            let hora = "14:00".to_string();
-            let prioridade = Prioridade::Alta;
-            let duracao = 60;
+        #[ink::test]
+        fn test_historico() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            definir_data_do_bloco(1, 1, 2025);
+            let mut agenda = Agenda::new();
+            let contato = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "111".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            assert!(agenda.historico_compromisso(reuniao).is_empty());
+
+            // Bob remarca a reunião
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            definir_data_do_bloco(2, 1, 2025);
+            agenda
+                .atualizar_compromisso(
+                    reuniao,
+                    NovoCompromisso {
+                        titulo: "Reunião".to_string(),
+                        data: "11/01/2025".to_string(),
+                        hora: "14:00".to_string(),
+                        prioridade: Prioridade::Alta,
+                        duracao: 60,
+                        detalhes: DetalhesCompromisso::default(),
+                    },
+                )
+                .unwrap();
+            agenda.confirmar_compromisso(reuniao, None).unwrap();
+            let historico = agenda.historico_compromisso(reuniao);
+            assert_eq!(historico.len(), 2);
+            assert_eq!(historico[0].numero, 1);
+            assert_eq!(historico[0].compromisso.data, "10/01/2025");
+            assert_eq!(historico[0].editor, accounts.bob);
+            assert_eq!(historico[0].editado_em, timestamp("02/01/2025", "00:00"));
+
+            // Reverter volta o conteúdo, mas mantém o status atual
+            agenda.reverter_compromisso(reuniao, 1).unwrap();
+            let compromisso = agenda.ler_compromisso(reuniao).unwrap();
+            assert_eq!(compromisso.data, "10/01/2025");
+            assert_eq!(compromisso.hora, "09:00");
+            assert_eq!(compromisso.status, StatusCompromisso::Confirmado);
+            assert_eq!(agenda.historico_compromisso(reuniao).len(), 3);
+            assert!(agenda.reverter_compromisso(reuniao, 9).is_err());
+
+            // Lembretes e participantes também geram revisões
+            agenda.definir_lembretes(reuniao, vec![60, 10]).unwrap();
+            agenda.confirmar_lembrete(reuniao, 60).unwrap();
+            agenda.adicionar_participante(reuniao, contato).unwrap();
+            let historico = agenda.historico_compromisso(reuniao);
+            assert_eq!(historico.len(), 5);
+            assert!(historico[3].compromisso.lembretes.is_empty());
+            assert!(historico[4].compromisso.participantes.is_empty());
+
+            // Reverter mantém os participantes e só as confirmações dos
+            // lembretes que continuam definidos
+            agenda.reverter_compromisso(reuniao, 5).unwrap();
+            assert_eq!(agenda.lembretes_confirmados.get(reuniao), Some(vec![60]));
+            agenda.reverter_compromisso(reuniao, 4).unwrap();
+            let compromisso = agenda.ler_compromisso(reuniao).unwrap();
+            assert!(compromisso.lembretes.is_empty());
+            assert_eq!(compromisso.participantes, vec![contato]);
+            assert_eq!(agenda.lembretes_confirmados.get(reuniao), None);
+            agenda.definir_lembretes(reuniao, vec![60]).unwrap();
+            assert_eq!(agenda.lembretes_confirmados.get(reuniao), None);
+            assert_eq!(
+                agenda.compromissos_do_contato(contato),
+                vec![(reuniao, agenda.ler_compromisso(reuniao).unwrap())]
+            );
 
-            let result = agenda.criar_compromisso(titulo, data, hora, prioridade, duracao);
-            assert!(
-                result.is_err(),
-                "Compromisso não deve ser criado com data inválida"
+            // Excluir e restaurar o participante também geram revisões
+            let revisoes = agenda.historico_compromisso(reuniao).len();
+            assert!(agenda.deletar_contato(contato));
+            let historico = agenda.historico_compromisso(reuniao);
+            assert_eq!(historico.len(), revisoes + 1);
+            assert_eq!(
+                historico.last().unwrap().compromisso.participantes,
+                vec![contato]
+            );
+            assert!(agenda
+                .ler_compromisso(reuniao)
+                .unwrap()
+                .participantes
+                .is_empty());
+            agenda.restaurar_contato(contato).unwrap();
+            let historico = agenda.historico_compromisso(reuniao);
+            assert_eq!(historico.len(), revisoes + 2);
+            assert!(historico
+                .last()
+                .unwrap()
+                .compromisso
+                .participantes
+                .is_empty());
+            assert_eq!(
+                agenda.ler_compromisso(reuniao).unwrap().participantes,
+                vec![contato]
+            );
+
+            // Contatos
+            agenda
+                .adicionar_tag_contato(contato, "vip".to_string())
+                .unwrap();
+            agenda
+                .atualizar_contato(
+                    contato,
+                    "Ana Souza".to_string(),
+                    "222".to_string(),
+                    31,
+                    "01/01/1994".to_string(),
+                    Categoria::Familiar,
+                )
+                .unwrap();
+            agenda.reverter_contato(contato, 1).unwrap();
+            let restaurado = agenda.ler_contato(contato).unwrap();
+            assert_eq!(restaurado.nome, "Ana");
+            assert!(restaurado.tags.is_empty());
+            assert_eq!(agenda.contato_por_telefone("111".to_string())[0].0, contato);
+            assert!(agenda.contato_por_telefone("222".to_string()).is_empty());
+
+            // Profundidade limitada
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            agenda.definir_profundidade_historico(2).unwrap();
+            agenda
+                .adicionar_tag_contato(contato, "amiga".to_string())
+                .unwrap();
+            let historico = agenda.historico_contato(contato);
+            assert_eq!(
+                historico.iter().map(|r| r.numero).collect::<Vec<_>>(),
+                vec![3, 4]
             );
+            assert!(agenda.reverter_contato(contato, 1).is_err());
+
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.definir_profundidade_historico(5).is_err());
         }
 
         #[ink::test]
-        fn test_atualizar_compromisso() {
+        fn test_atualizacoes_parciais() {
             let mut agenda = Agenda::new();
+            let contato = agenda
+                .criar_contato(
+                    "Ana".to_string(),
+                    "111".to_string(),
+                    30,
+                    "01/01/1994".to_string(),
+                    Categoria::Amigo,
+                )
+                .unwrap();
+            agenda
+                .adicionar_telefone(
+                    contato,
+                    Telefone {
+                        rotulo: Rotulo::Trabalho,
+                        numero: "222".to_string(),
+                        principal: false,
+                    },
+                )
+                .unwrap();
 
-            // Cria um compromisso válido
-            let titulo = "Reunião".to_string();
-            let data = "01/01/2025".to_string();
-            let hora = "14:00".to_string();
-            let prioridade = Prioridade::Alta;
-            let duracao = 60;
+            agenda
+                .atualizar_contato_parcial(
+                    contato,
+                    AlteracaoContato {
+                        telefone: Some("(33) 3".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let lido = agenda.ler_contato(contato).unwrap();
+            assert_eq!(lido.nome, "Ana");
+            assert_eq!(lido.telefone, "333");
+            assert_eq!(lido.telefones.len(), 2);
+            assert_eq!(lido.categoria, Categoria::Amigo);
+            assert!(agenda.contato_por_telefone("111".to_string()).is_empty());
+
+            // Apenas os campos informados são validados
+            assert!(agenda
+                .atualizar_contato_parcial(
+                    contato,
+                    AlteracaoContato {
+                        data_aniversario: Some("31/02/1994".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .is_err());
+            assert!(agenda
+                .atualizar_contato_parcial(99, AlteracaoContato::default())
+                .is_err());
 
-            let id = agenda
+            let reuniao = agenda
                 .criar_compromisso(
-                    titulo.clone(),
-                    data.clone(),
-                    hora.clone(),
-                    prioridade,
-                    duracao,
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso {
+                        descricao: Some("Pauta".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .0;
+            agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "10/01/2025".to_string(),
+                    "12:00".to_string(),
+                    Prioridade::Baixa,
+                    60,
+                    DetalhesCompromisso::default(),
                 )
                 .unwrap();
-
-            // Atualiza o compromisso com novas informações
-            let new_titulo = "Conferência".to_string();
-            let new_data = "02/01/2025".to_string();
-            let new_hora = "10:00".to_string();
-            let new_prioridade = Prioridade::Media;
-            let new_duracao = 90;
-
-            let update_result = agenda.atualizar_compromisso(
-                id,
-                new_titulo.clone(),
-                new_data.clone(),
-                new_hora.clone(),
-                new_prioridade.clone(),
-                new_duracao,
+            agenda
+                .atualizar_compromisso_parcial(
+                    reuniao,
+                    AlteracaoCompromisso {
+                        hora: Some("10:00".to_string()),
+                        descricao: Some(None),
+                        link_reuniao: Some(Some("https://x.com/r".to_string())),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            let lido = agenda.ler_compromisso(reuniao).unwrap();
+            assert_eq!(lido.titulo, "Reunião");
+            assert_eq!(lido.hora, "10:00");
+            assert_eq!(lido.detalhes.descricao, None);
+            assert_eq!(
+                lido.detalhes.link_reuniao,
+                Some("https://x.com/r".to_string())
             );
-            assert!(update_result.is_ok(), "Falha ao atualizar compromisso");
 
-            let updated_compromisso = agenda
-                .ler_compromisso(id)
-                .expect("O compromisso deve existir");
-            assert_eq!(updated_compromisso.titulo, new_titulo);
-            assert_eq!(updated_compromisso.data, new_data);
-            assert_eq!(updated_compromisso.hora, new_hora);
-            assert_eq!(updated_compromisso.prioridade, new_prioridade);
-            assert_eq!(updated_compromisso.duracao, new_duracao);
+            // Remarcações continuam sujeitas às regras de conflito
+            agenda
+                .definir_politica_conflito(Politica::Rejeitar)
+                .unwrap();
+            assert!(agenda
+                .atualizar_compromisso_parcial(
+                    reuniao,
+                    AlteracaoCompromisso {
+                        hora: Some("12:30".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .is_err());
+            assert!(agenda
+                .atualizar_compromisso_parcial(
+                    reuniao,
+                    AlteracaoCompromisso {
+                        hora: Some("25:00".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .is_err());
+            agenda
+                .atualizar_compromisso_parcial(
+                    reuniao,
+                    AlteracaoCompromisso {
+                        titulo: Some("Reunião geral".to_string()),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            assert_eq!(agenda.ler_compromisso(reuniao).unwrap().hora, "10:00");
         }
 
         #[ink::test]
-        fn test_deletar_contato() {
+        fn test_operacoes_em_lote() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
             let mut agenda = Agenda::new();
+            let novo = |nome: &str, data: &str| NovoContato {
+                nome: nome.to_string(),
+                telefone: format!("9{}", nome.len()),
+                idade: 30,
+                data_aniversario: data.to_string(),
+                categoria: Categoria::Colega,
+            };
 
-            // Cria um contato válido
-            let nome = "John Doe".to_string();
-            let telefone = "123456789".to_string();
-            let idade = 30;
-            let data_aniversario = "01/01/1990".to_string();
-            let categoria = Categoria::Amigo;
-
-            let id = agenda
-                .criar_contato(nome, telefone, idade, data_aniversario, categoria)
+            // Melhor esforço grava os válidos e informa o erro de cada item
+            let resultados = agenda
+                .criar_contatos_em_lote(
+                    vec![
+                        novo("Ana", "01/01/1994"),
+                        novo("Bia", "31/02/1994"),
+                        novo("Carla", "03/03/1990"),
+                    ],
+                    ModoLote::MelhorEsforco,
+                )
                 .unwrap();
+            assert_eq!(resultados[0], Ok(0));
+            assert!(resultados[1].is_err());
+            assert_eq!(resultados[2], Ok(1));
+            assert_eq!(agenda.listar_contatos().len(), 2);
+
+            // Tudo ou nada aponta o item com erro. Desfazer o "Davi" já gravado
+            // cabe ao revert do ink! quando a mensagem retorna `Err`, que não
+            // acontece nos testes off-chain; aqui só o erro é verificado.
+            assert_eq!(
+                agenda.criar_contatos_em_lote(
+                    vec![novo("Davi", "01/01/1994"), novo("", "01/01/1994")],
+                    ModoLote::TudoOuNada,
+                ),
+                Err("Item 1: Nome não pode estar vazio".to_string())
+            );
 
-            // Deleta o contato
-            let delete_result = agenda.deletar_contato(id);
-            assert!(delete_result, "O contato deve ser deletado");
+            let resultados = agenda
+                .atualizar_contatos_em_lote(
+                    vec![
+                        (
+                            0,
+                            AlteracaoContato {
+                                idade: Some(31),
+                                ..Default::default()
+                            },
+                        ),
+                        (42, AlteracaoContato::default()),
+                    ],
+                    ModoLote::MelhorEsforco,
+                )
+                .unwrap();
+            assert_eq!(resultados[0], Ok(()));
+            assert!(resultados[1].is_err());
+            assert_eq!(agenda.ler_contato(0).unwrap().idade, 31);
+
+            let compromisso = |hora: &str| NovoCompromisso {
+                titulo: "Consulta".to_string(),
+                data: "10/01/2025".to_string(),
+                hora: hora.to_string(),
+                prioridade: Prioridade::Media,
+                duracao: 30,
+                detalhes: DetalhesCompromisso::default(),
+            };
+            let criados = agenda
+                .criar_compromissos_em_lote(
+                    vec![compromisso("09:00"), compromisso("10:00")],
+                    ModoLote::TudoOuNada,
+                )
+                .unwrap();
+            assert_eq!(
+                criados,
+                vec![Ok((0, Avisos::default())), Ok((1, Avisos::default()))]
+            );
+            let resultados = agenda
+                .atualizar_compromissos_em_lote(
+                    vec![(
+                        1,
+                        AlteracaoCompromisso {
+                            hora: Some("11:00".to_string()),
+                            ..Default::default()
+                        },
+                    )],
+                    ModoLote::TudoOuNada,
+                )
+                .unwrap();
+            assert_eq!(resultados, vec![Ok(Avisos::default())]);
+            assert_eq!(agenda.ler_compromisso(1).unwrap().hora, "11:00");
 
-            // Garante que o contato não existe mais
-            let deleted_contato = agenda.ler_contato(id);
-            assert!(
-                deleted_contato.is_none(),
-                "O contato não deve existir após a exclusão"
+            let resultados = agenda
+                .deletar_compromissos_em_lote(vec![0, 0], ModoLote::MelhorEsforco)
+                .unwrap();
+            assert_eq!(resultados[0], Ok(()));
+            assert!(resultados[1].is_err());
+            assert_eq!(
+                agenda.deletar_contatos_em_lote(vec![0, 1], ModoLote::TudoOuNada),
+                Ok(vec![Ok(()), Ok(())])
             );
+            assert_eq!(agenda.lixeira_de_contatos().len(), 2);
+
+            // Tamanho máximo configurável pelo dono
+            agenda.definir_tamanho_maximo_lote(1).unwrap();
+            assert!(agenda
+                .deletar_compromissos_em_lote(vec![1, 2], ModoLote::MelhorEsforco)
+                .is_err());
+            assert!(agenda.definir_tamanho_maximo_lote(0).is_err());
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert!(agenda.definir_tamanho_maximo_lote(10).is_err());
         }
 
         #[ink::test]
-        fn test_deletar_compromisso() {
+        fn test_duplicados_e_mesclagem() {
             let mut agenda = Agenda::new();
+            let mut criar = |nome: &str, telefone: &str, data: &str| {
+                agenda
+                    .criar_contato(
+                        nome.to_string(),
+                        telefone.to_string(),
+                        30,
+                        data.to_string(),
+                        Categoria::Amigo,
+                    )
+                    .unwrap()
+            };
+            let jose = criar("José da Silva", "111", "05/05/1990");
+            let outro_jose = criar("  jose  DA silva ", "222", "05/05/1990");
+            let ana = criar("Ana", "(33) 3", "01/01/1994");
+            let ana_trabalho = criar("Ana Souza", "333", "02/02/1980");
+            let homonimo = criar("José da Silva", "444", "05/05/1991");
+
+            assert_eq!(
+                agenda.possiveis_duplicados(),
+                vec![
+                    PossivelDuplicado {
+                        contato_a: jose,
+                        contato_b: outro_jose,
+                        mesmo_telefone: false,
+                        mesmo_nome_e_aniversario: true,
+                    },
+                    PossivelDuplicado {
+                        contato_a: ana,
+                        contato_b: ana_trabalho,
+                        mesmo_telefone: true,
+                        mesmo_nome_e_aniversario: false,
+                    },
+                ]
+            );
+            assert!(!agenda
+                .possiveis_duplicados()
+                .iter()
+                .any(|p| p.contato_b == homonimo));
+
+            // Mesclar combina listas e move participações
+            agenda
+                .adicionar_email(
+                    outro_jose,
+                    Email {
+                        rotulo: Rotulo::Trabalho,
+                        endereco: "jose@exemplo.com".to_string(),
+                        principal: true,
+                    },
+                )
+                .unwrap();
+            agenda
+                .adicionar_tag_contato(outro_jose, "cliente".to_string())
+                .unwrap();
+            let reuniao = agenda
+                .criar_compromisso(
+                    "Reunião".to_string(),
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    Prioridade::Alta,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            agenda.adicionar_participante(reuniao, outro_jose).unwrap();
+            let almoco = agenda
+                .criar_compromisso(
+                    "Almoço".to_string(),
+                    "11/01/2025".to_string(),
+                    "12:00".to_string(),
+                    Prioridade::Baixa,
+                    60,
+                    DetalhesCompromisso::default(),
+                )
+                .unwrap()
+                .0;
+            agenda.adicionar_participante(almoco, outro_jose).unwrap();
+            assert!(agenda.deletar_compromisso(almoco));
+            let revisoes = agenda.historico_compromisso(reuniao).len();
+            let grupo = agenda.criar_grupo("Clientes".to_string()).unwrap();
+            agenda.adicionar_ao_grupo(grupo, outro_jose).unwrap();
+
+            assert!(agenda.mesclar_contatos(jose, jose).is_err());
+            assert!(agenda.mesclar_contatos(99, jose).is_err());
+            agenda.mesclar_contatos(outro_jose, jose).unwrap();
+
+            let mesclado = agenda.ler_contato(jose).unwrap();
+            assert_eq!(mesclado.nome, "José da Silva");
+            assert_eq!(mesclado.telefone, "111");
+            let numeros: Vec<&str> = mesclado
+                .telefones
+                .iter()
+                .map(|t| t.numero.as_str())
+                .collect();
+            assert_eq!(numeros, vec!["111", "222"]);
+            assert_eq!(mesclado.emails[0].endereco, "jose@exemplo.com");
+            assert_eq!(mesclado.tags, vec!["cliente"]);
+            assert_eq!(agenda.contato_por_telefone("222".to_string())[0].0, jose);
+
+            assert!(agenda.ler_contato(outro_jose).is_none());
+            assert_eq!(agenda.lixeira_de_contatos()[0].0, outro_jose);
+            assert_eq!(
+                agenda.ler_compromisso(reuniao).unwrap().participantes,
+                vec![jose]
+            );
+            assert_eq!(agenda.compromissos_do_contato(jose)[0].0, reuniao);
+            let historico = agenda.historico_compromisso(reuniao);
+            assert_eq!(historico.len(), revisoes + 1);
+            assert_eq!(
+                historico.last().unwrap().compromisso.participantes,
+                vec![outro_jose]
+            );
+            // Compromissos na lixeira também passam para o destino
+            assert_eq!(
+                agenda.lixeira_de_compromissos()[0]
+                    .1
+                    .compromisso
+                    .participantes,
+                vec![jose]
+            );
+            agenda.restaurar_compromisso(almoco).unwrap();
+            assert_eq!(
+                agenda.ler_compromisso(almoco).unwrap().participantes,
+                vec![jose]
+            );
+            assert_eq!(agenda.compromissos_do_contato(jose).len(), 2);
+            assert_eq!(agenda.ler_grupo(grupo).unwrap().membros, vec![jose]);
+            assert_eq!(agenda.contatos_com_tag("cliente".to_string())[0].0, jose);
+            assert!(agenda
+                .possiveis_duplicados()
+                .iter()
+                .all(|p| p.contato_a != jose || p.contato_b == homonimo));
+        }
 
-            // Cria um compromisso válido
-            let titulo = "Reunião".to_string();
-            let data = "01/01/2025".to_string();
-            let hora = "14:00".to_string();
-            let prioridade = Prioridade::Alta;
-            let duracao = 60;
+        #[ink::test]
+        fn test_deletar_compromisso_reservado() {
+            type E = ink::env::DefaultEnvironment;
+            let accounts = ink::env::test::default_accounts::<E>();
+            ink::env::test::set_callee::<E>(accounts.frank);
+            let mut agenda = Agenda::new();
+            definir_data_do_bloco(1, 1, 2025);
+            let disponibilidade = agenda
+                .publicar_disponibilidade(
+                    "10/01/2025".to_string(),
+                    "09:00".to_string(),
+                    "10:00".to_string(),
+                    30,
+                    0,
+                )
+                .unwrap();
 
-            let id = agenda
-                .criar_compromisso(titulo, data, hora, prioridade, duracao)
+            ink::env::test::set_caller::<E>(accounts.bob);
+            let reserva = agenda
+                .reservar_horario(disponibilidade, "09:00".to_string(), "Consulta".to_string())
                 .unwrap();
+            let compromisso = agenda.ler_reserva(reserva).unwrap().compromisso_id;
+
+            // O dono não contorna a reserva deletando o compromisso
+            ink::env::test::set_caller::<E>(accounts.alice);
+            assert!(!agenda.deletar_compromisso(compromisso));
+            assert_eq!(
+                agenda.deletar_compromissos_em_lote(vec![compromisso], ModoLote::TudoOuNada),
+                Err("Item 0: Compromisso reservado deve ser cancelado pela reserva".to_string())
+            );
+            assert!(agenda.ler_compromisso(compromisso).is_some());
+            assert_eq!(
+                agenda.ler_reserva(reserva).unwrap().situacao,
+                SituacaoReserva::Ativa
+            );
 
-            // Deleta o compromisso
-            let delete_result = agenda.deletar_compromisso(id);
-            assert!(delete_result, "O compromisso deve ser deletado");
+            // O horário continua ocupado para outros reservantes
+            ink::env::test::set_caller::<E>(accounts.charlie);
+            assert!(agenda
+                .reservar_horario(disponibilidade, "09:00".to_string(), "Outra".to_string())
+                .is_err());
+            assert_eq!(
+                agenda.horarios_disponiveis(disponibilidade).unwrap(),
+                vec!["09:30"]
+            );
+        }
 
-            // Garante que o compromisso não existe mais
-            let deleted_compromisso = agenda.ler_compromisso(id);
-            assert!(
-                deleted_compromisso.is_none(),
-                "O compromisso não deve existir após a exclusão"
+        #[ink::test]
+        fn test_indice_de_dias() {
+            let mut agenda = Agenda::new();
+            let criar = |agenda: &mut Agenda, data: &str| {
+                agenda
+                    .criar_compromisso(
+                        "Reunião".to_string(),
+                        data.to_string(),
+                        "09:00".to_string(),
+                        Prioridade::Media,
+                        30,
+                        DetalhesCompromisso::default(),
+                    )
+                    .unwrap()
+                    .0
+            };
+            let distante = criar(&mut agenda, "10/01/2030");
+            let primeiro = criar(&mut agenda, "10/01/2025");
+            let mesmo_bloco = criar(&mut agenda, "11/01/2025");
+            assert_eq!(agenda.blocos_com_compromissos.get_or_default().len(), 2);
+
+            let ids = |agenda: &Agenda| {
+                agenda
+                    .filtrar_compromissos(FiltroCompromissos::default(), 0, 10)
+                    .unwrap()
+                    .itens
+                    .into_iter()
+                    .map(|(id, _)| id)
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(ids(&agenda), vec![primeiro, mesmo_bloco, distante]);
+
+            // O bloco sai do índice quando o último dia dele fica vazio
+            assert!(agenda.deletar_compromisso(distante));
+            assert_eq!(agenda.blocos_com_compromissos.get_or_default().len(), 1);
+            assert!(agenda.deletar_compromisso(primeiro));
+            assert_eq!(ids(&agenda), vec![mesmo_bloco]);
+            assert_eq!(
+                agenda.dias_com_compromissos(i64::MIN, i64::MAX),
+                vec![Agenda::dias_desde_epoch(11, 1, 2025)]
             );
         }
 
         #[ink::test]
-        fn test_meses_com_30_dias_invalidos() {
+        fn test_purga_da_lixeira() {
+            let accounts = ink::env::test::default_accounts::<ink::env::DefaultEnvironment>();
+            definir_data_do_bloco(1, 1, 2025);
             let mut agenda = Agenda::new();
-            let datas_invalidas_30 = vec!["04/31/1990", "06/31/1995", "09/31/2000", "11/31/2020"];
-            for data in datas_invalidas_30 {
-                let result = agenda.criar_contato(
-                    "Teste".to_string(),
-                    "123456789".to_string(),
-                    30,
-                    data.to_string(),
-                    Categoria::Colega,
-                );
-                assert!(
-                    result.is_err(),
-                    "Contato criado com data inválida: {}",
-                    data
-                );
+            agenda.definir_retencao_lixeira(1).unwrap();
+            let ids: Vec<u32> = (0..MAX_PURGA + 5)
+                .map(|i| {
+                    agenda
+                        .criar_contato(
+                            "Ana".to_string(),
+                            format!("1{i}"),
+                            30,
+                            "01/01/1994".to_string(),
+                            Categoria::Amigo,
+                        )
+                        .unwrap()
+                })
+                .collect();
+            for id in &ids {
+                assert!(agenda.deletar_contato(*id));
+            }
+            // Restaurar deixa a entrada da fila obsoleta, sem apagar o contato
+            agenda.restaurar_contato(ids[0]).unwrap();
+            assert_eq!(agenda.lixeira_de_contatos().len(), ids.len() - 1);
+
+            // Qualquer conta dispara a purga, limitada por chamada
+            definir_data_do_bloco(2, 1, 2025);
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.bob);
+            assert_eq!(agenda.purgar_lixeira(), MAX_PURGA - 1);
+            assert_eq!(agenda.purgar_lixeira(), 5);
+            assert_eq!(agenda.purgar_lixeira(), 0);
+            assert!(agenda.ler_contato(ids[0]).is_some());
+            assert!(agenda.restaurar_contato(ids[1]).is_err());
+            assert_eq!(
+                agenda.fila_contatos_na_lixeira,
+                FilaLixeira {
+                    inicio: MAX_PURGA + 5,
+                    fim: MAX_PURGA + 5
+                }
+            );
+
+            // Esvaziar também é limitado por chamada, mesmo dentro do prazo
+            for i in 0..MAX_PURGA + 5 {
+                let id = agenda
+                    .criar_contato(
+                        "Bia".to_string(),
+                        format!("2{i}"),
+                        30,
+                        "01/01/1994".to_string(),
+                        Categoria::Amigo,
+                    )
+                    .unwrap();
+                assert!(agenda.deletar_contato(id));
             }
+            ink::env::test::set_caller::<ink::env::DefaultEnvironment>(accounts.alice);
+            assert_eq!(agenda.esvaziar_lixeira(), Ok(MAX_PURGA));
+            assert_eq!(agenda.lixeira_de_contatos().len(), 5);
+            assert_eq!(agenda.esvaziar_lixeira(), Ok(5));
+            assert_eq!(agenda.esvaziar_lixeira(), Ok(0));
+            assert!(agenda.lixeira_de_contatos().is_empty());
         }
 
         #[ink::test]
-        fn test_datas_gerais_invalidas() {
+        fn test_limites_de_duracao() {
             let mut agenda = Agenda::new();
-            let data_invalida = "13/32/2023"; // Mês e dia inválidos
-            let result = agenda.criar_contato(
-                "Teste".to_string(),
-                "123456789".to_string(),
-                30,
-                data_invalida.to_string(),
-                Categoria::Amigo,
-            );
-            assert!(
-                result.is_err(),
-                "Contato criado com data inválida: {}",
-                data_invalida
+            let criar = |agenda: &mut Agenda, data: &str, duracao: i32| {
+                agenda.criar_compromisso(
+                    "Viagem".to_string(),
+                    data.to_string(),
+                    "08:00".to_string(),
+                    Prioridade::Media,
+                    duracao,
+                    DetalhesCompromisso::default(),
+                )
+            };
+            assert!(criar(&mut agenda, "10/01/2025", 0).is_err());
+            assert!(criar(&mut agenda, "10/01/2025", -30).is_err());
+            assert!(criar(&mut agenda, "10/01/2025", MAX_DURACAO + 1).is_err());
+            let curto = criar(&mut agenda, "10/01/2025", 60).unwrap().0;
+            assert!(agenda
+                .atualizar_compromisso_parcial(
+                    curto,
+                    AlteracaoCompromisso {
+                        duracao: Some(0),
+                        ..Default::default()
+                    },
+                )
+                .is_err());
+            let resultados = agenda
+                .criar_compromissos_em_lote(
+                    vec![NovoCompromisso {
+                        titulo: "Viagem".to_string(),
+                        data: "10/01/2025".to_string(),
+                        hora: "08:00".to_string(),
+                        prioridade: Prioridade::Media,
+                        duracao: MAX_DURACAO + 1,
+                        detalhes: DetalhesCompromisso::default(),
+                    }],
+                    ModoLote::MelhorEsforco,
+                )
+                .unwrap();
+            assert!(resultados[0].is_err());
+            assert_eq!(agenda.maior_recuo, 1);
+
+            // A busca por conflitos volta a examinar menos dias quando o
+            // compromisso mais longo deixa de contar
+            let longo = criar(&mut agenda, "20/01/2025", MAX_DURACAO).unwrap().0;
+            let outro_longo = criar(&mut agenda, "01/02/2025", 3 * MINUTOS_POR_DIA as i32)
+                .unwrap()
+                .0;
+            assert_eq!(agenda.maior_recuo, 7);
+            assert_eq!(
+                agenda
+                    .verificar_conflitos("26/01/2025".to_string(), "09:00".to_string(), 30, None)
+                    .unwrap(),
+                vec![longo]
             );
+            agenda.cancelar_compromisso(longo, None).unwrap();
+            assert_eq!(agenda.maior_recuo, 3);
+            assert!(agenda.deletar_compromisso(outro_longo));
+            assert_eq!(agenda.maior_recuo, 1);
         }
     }
 
@@ -895,6 +8286,7 @@ mod agenda {
                 hora.clone(),
                 prioridade.clone(),
                 duracao,
+                DetalhesCompromisso::default(),
             );
 
             let id = client
@@ -903,7 +8295,8 @@ mod agenda {
                 .await
                 .expect("Failed to create an appointment")
                 .return_value()
-                .unwrap();
+                .unwrap()
+                .0;
 
             let compromisso = call_builder.ler_compromisso(id);
             let created_compromisso: Option<Compromisso> = client
@@ -953,6 +8346,7 @@ mod agenda {
                 hora.clone(),
                 prioridade.clone(),
                 duracao,
+                DetalhesCompromisso::default(),
             );
 
             let id = client
@@ -961,7 +8355,8 @@ mod agenda {
                 .await
                 .expect("Failed to create an appointment")
                 .return_value()
-                .unwrap();
+                .unwrap()
+                .0;
 
             // Atualiza o compromisso
             let new_titulo = "Conferência".to_string();
@@ -972,11 +8367,14 @@ mod agenda {
 
             let update = call_builder.atualizar_compromisso(
                 id,
-                new_titulo.clone(),
-                new_data.clone(),
-                new_hora.clone(),
-                new_prioridade.clone(),
-                new_duracao,
+                NovoCompromisso {
+                    titulo: new_titulo.clone(),
+                    data: new_data.clone(),
+                    hora: new_hora.clone(),
+                    prioridade: new_prioridade.clone(),
+                    duracao: new_duracao,
+                    detalhes: DetalhesCompromisso::default(),
+                },
             );
 
             let update_result = client
@@ -1036,6 +8434,7 @@ mod agenda {
                 hora.clone(),
                 prioridade.clone(),
                 duracao,
+                DetalhesCompromisso::default(),
             );
             let id = client
                 .call(&ink_e2e::alice(), &set)
@@ -1043,7 +8442,8 @@ mod agenda {
                 .await
                 .expect("Failed to create an appointment")
                 .return_value()
-                .unwrap();
+                .unwrap()
+                .0;
 
             // Delete the appointment
             let delete = call_builder.deletar_compromisso(id);